@@ -0,0 +1,206 @@
+use crate::report::{Entry, Report};
+use amethyst::config::ConfigError;
+use amethyst::prelude::Config;
+use dsf_checks::reachability::jump_landings;
+use dsf_checks::scenarios::ScenarioFile;
+use dsf_checks::solver::{InconclusiveReason, Solver, SolverResult};
+use dsf_core::components::Pos;
+use dsf_core::levels::{load_tile_definitions, migrate_level, Level, CURRENT_FORMAT_VERSION};
+use dsf_core::resources::load_movement_config;
+use dsf_core::utility::files::get_levels_dir;
+use std::fs;
+use std::path::PathBuf;
+
+/// Checks that each level file can be loaded and follows the rules that every level must follow.
+pub fn validate(level_files: &[PathBuf]) -> Report {
+    let tile_defs = match load_tile_definitions() {
+        Ok(tile_defs) => tile_defs,
+        Err(err) => return load_failure("validate", "tile definitions", err),
+    };
+    let entries = level_files
+        .iter()
+        .map(|level_file| {
+            let subject = level_file.to_string_lossy();
+            match Level::load_migrated(level_file) {
+                Ok(level) => {
+                    let violations = level.validate(&tile_defs);
+                    violations
+                        .iter()
+                        .fold(
+                            Entry::new(subject, violations.is_empty()),
+                            |entry, violation| entry.with_message(format!("{}", violation)),
+                        )
+                        .with_data(
+                            serde_json::to_value(&violations)
+                                .expect("Failed to serialise violations."),
+                        )
+                }
+                Err(err) => Entry::new(subject, false).with_message(format!("{}", err)),
+            }
+        })
+        .collect();
+    Report::new("validate", entries)
+}
+
+/// Searches for the shortest solution of the level.
+pub fn solve(level_file: &PathBuf, max_states: Option<usize>) -> Report {
+    let subject = level_file.to_string_lossy();
+    let (level, tile_defs) = match (Level::load_migrated(level_file), load_tile_definitions()) {
+        (Ok(level), Ok(tile_defs)) => (level, tile_defs),
+        (Err(err), _) | (_, Err(err)) => return load_failure("solve", &subject, err),
+    };
+    let mut solver = Solver::new(level, tile_defs, load_movement_config());
+    if let Some(max_states) = max_states {
+        solver = solver.with_max_states(max_states);
+    }
+    let entry = match solver.solve() {
+        SolverResult::Solved(actions) => actions
+            .iter()
+            .fold(
+                Entry::new(subject, true)
+                    .with_message(format!("Solved in {} actions:", actions.len())),
+                |entry, action| entry.with_message(format!("{:?}", action)),
+            )
+            .with_data(serde_json::to_value(&actions).expect("Failed to serialise solution.")),
+        SolverResult::Unsolvable { explored } => Entry::new(subject, false)
+            .with_message(format!("No solution exists. Explored {} states.", explored)),
+        SolverResult::Inconclusive {
+            reason: InconclusiveReason::StateLimit,
+            explored,
+        } => Entry::new(subject, false)
+            .with_message(format!("Gave up after exploring {} states.", explored)),
+        SolverResult::Inconclusive {
+            reason: InconclusiveReason::NoInitialState,
+            ..
+        } => Entry::new(subject, false)
+            .with_message("The player never stands still after the level is loaded."),
+        SolverResult::Inconclusive {
+            reason: InconclusiveReason::UnsupportedTiles(keys),
+            ..
+        } => Entry::new(subject, false).with_message(format!(
+            "Cannot solve levels that contain these tiles: {}.",
+            keys.join(", ")
+        )),
+    };
+    Report::new("solve", vec![entry])
+}
+
+/// Lists every position the player can land on by jumping from the given position.
+pub fn reachability(level_file: &PathBuf, from: Pos) -> Report {
+    let subject = level_file.to_string_lossy();
+    let (level, tile_defs) = match (Level::load_migrated(level_file), load_tile_definitions()) {
+        (Ok(level), Ok(tile_defs)) => (level, tile_defs),
+        (Err(err), _) | (_, Err(err)) => return load_failure("reachability", &subject, err),
+    };
+    let dimens = match tile_defs.player_dimens() {
+        Some(dimens) => dimens,
+        None => {
+            let entry =
+                Entry::new(subject, false).with_message("There is no player tile definition.");
+            return Report::new("reachability", vec![entry]);
+        }
+    };
+    let landings = jump_landings(&level, &tile_defs, &load_movement_config(), from, dimens);
+    let entry = if landings.is_empty() {
+        Entry::new(subject, false).with_message(format!(
+            "The player cannot jump from ({}, {}).",
+            from.x, from.y
+        ))
+    } else {
+        landings
+            .iter()
+            .fold(
+                Entry::new(subject, true).with_message(format!(
+                    "Jumping from ({}, {}), the player can land on:",
+                    from.x, from.y
+                )),
+                |entry, pos| entry.with_message(format!("({}, {})", pos.x, pos.y)),
+            )
+            .with_data(serde_json::to_value(&landings).expect("Failed to serialise landings."))
+    };
+    Report::new("reachability", vec![entry])
+}
+
+/// Runs every scenario in the given scenario file.
+pub fn run_scenarios(scenario_file: &PathBuf) -> Report {
+    let subject = scenario_file.to_string_lossy();
+    let outcomes = match ScenarioFile::load(scenario_file)
+        .and_then(|scenarios| scenarios.run_all(&load_movement_config()))
+    {
+        Ok(outcomes) => outcomes,
+        Err(err) => return load_failure("run-scenarios", &subject, err),
+    };
+    let entries = outcomes
+        .into_iter()
+        .map(|outcome| {
+            let end = match (outcome.landed, outcome.end_pos) {
+                (true, Some(pos)) => format!("Landed at ({}, {}).", pos.x, pos.y),
+                _ => "Never landed.".to_string(),
+            };
+            let mut entry = Entry::new(outcome.name.clone(), outcome.passed).with_message(end);
+            if let Some((min_x, max_x)) = outcome.x_range {
+                entry = entry.with_message(format!("Moved between x={} and x={}.", min_x, max_x));
+            }
+            entry.with_data(serde_json::to_value(&outcome).expect("Failed to serialise outcome."))
+        })
+        .collect();
+    Report::new("run-scenarios", entries)
+}
+
+/// Rewrites each level file in the newest version of the level format. Files that are already in
+/// the newest version are left untouched.
+///
+/// This changes the files in place. Saves, replays and best runs stay valid, because they are
+/// tied to what a level describes rather than to the exact contents of its file.
+pub fn migrate(level_files: &[PathBuf]) -> Report {
+    let entries = level_files
+        .iter()
+        .map(|level_file| {
+            let subject = level_file.to_string_lossy();
+            let migrated = fs::read(level_file)
+                .map_err(ConfigError::from)
+                .and_then(|contents| migrate_level(&contents));
+            match migrated {
+                Ok((_, version)) if version == CURRENT_FORMAT_VERSION => Entry::new(subject, true)
+                    .with_message(format!("Already in format version {}.", version)),
+                Ok((level, version)) => match level.write(level_file) {
+                    Ok(()) => Entry::new(subject, true).with_message(format!(
+                        "Upgraded from format version {} to {}.",
+                        version, CURRENT_FORMAT_VERSION
+                    )),
+                    Err(err) => {
+                        Entry::new(subject, false).with_message(format!("Failed to write: {}", err))
+                    }
+                },
+                Err(err) => {
+                    Entry::new(subject, false).with_message(format!("Failed to load: {}", err))
+                }
+            }
+        })
+        .collect();
+    Report::new("migrate", entries)
+}
+
+/// Every level file in the levels directory.
+pub fn all_level_files() -> Vec<PathBuf> {
+    let mut level_files: Vec<PathBuf> = fs::read_dir(get_levels_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.is_file()
+                        && path
+                            .extension()
+                            .map_or(false, |extension| extension == "ron")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    level_files.sort();
+    level_files
+}
+
+fn load_failure(command: &str, subject: &str, err: impl std::fmt::Display) -> Report {
+    let entry = Entry::new(subject, false).with_message(format!("Failed to load: {}", err));
+    Report::new(command, vec![entry])
+}
@@ -23,4 +23,7 @@ pub enum MovementTest {
     Jump2Wide,
     /// This test will prove that the player can NEVER make a jump across a 4-wide gap.
     Jump4Wide,
+    /// Runs every scenario in movement/jump_scenarios.ron in a headless simulation and logs the
+    /// results.
+    JumpScenarios,
 }
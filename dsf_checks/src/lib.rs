@@ -1,21 +1,27 @@
 #![forbid(unsafe_code)]
 
-//! This crate is not really used right now. The plan is to be able to run automated tests
-//! with AIs playing the role of the player to prove that the player will or will not be able to
-//! make certain jumps, etc.
+//! This crate runs automated checks with AIs playing the role of the player, to prove that the
+//! player will or will not be able to make certain jumps, etc.
 //!
 //! Being able to guarantee that a player cannot make a certain jump is of course very important in
 //! a puzzle game, because these sorts of bugs could make puzzles trivially easy.
 //!
-//! This crate can also eventually be leveraged to create automated tests that prove that a certain
-//! puzzle is solvable.
-
-// TODO: Change this crate to a bin.
+//! The reachability module performs every possible jump in a headless Simulation to find out
+//! exactly where a player can land.
+//!
+//! The solver module searches the discrete states of a level to prove that it can or cannot be
+//! solved. It drives the real game play systems through a headless Simulation, so it always
+//! agrees with the game.
+//!
+//! The checks can be run from the command line with the dsf_checks binary.
 
 #[macro_use]
 extern crate log;
 
 pub mod components;
+pub mod reachability;
 pub mod resources;
+pub mod scenarios;
+pub mod solver;
 pub mod states;
 pub mod systems;
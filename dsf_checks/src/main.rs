@@ -0,0 +1,118 @@
+#![forbid(unsafe_code)]
+
+//! Command-line tool that runs automated checks on levels and movement.
+//!
+//! Run it before committing changes to the levels. It exits with a non-zero exit code if any of
+//! the checks fail.
+
+mod commands;
+mod report;
+
+use amethyst::utils::application_root_dir;
+use dsf_core::components::Pos;
+use dsf_core::utility::files::ROOT_DIR_ENV_VAR;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: dsf_checks [--json] [--root <dir>] <command> [arguments]
+
+Commands:
+    validate <level.ron>...                 Check that the levels load and are well-formed.
+    solve <level.ron> [--max-states <n>]    Find the shortest solution of the level.
+    reachability <level.ron> --from <x,y>   List where the player can land by jumping.
+    run-scenarios <scenarios.ron>           Run the movement scenarios in the given file.
+    migrate [<level.ron>...]                Rewrite the levels in the newest level format.
+                                            Without arguments, rewrites every level.
+
+Options:
+    --json          Print the results as JSON instead of human-readable text.
+    --root <dir>    The game's root directory, which contains the assets directory.";
+
+fn main() {
+    let mut json = false;
+    let mut root = None;
+    let mut args = vec![];
+    let mut raw_args = env::args().skip(1);
+    while let Some(arg) = raw_args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--root" => root = Some(raw_args.next().unwrap_or_else(|| usage_error())),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => args.push(arg),
+        }
+    }
+    set_root_dir(root);
+
+    let report = match args.split_first() {
+        Some((command, rest)) => match command.as_str() {
+            "validate" if !rest.is_empty() => {
+                commands::validate(&rest.iter().map(PathBuf::from).collect::<Vec<PathBuf>>())
+            }
+            "solve" => match rest {
+                [level] => commands::solve(&PathBuf::from(level), None),
+                [level, flag, max_states] if flag == "--max-states" => commands::solve(
+                    &PathBuf::from(level),
+                    Some(max_states.parse().unwrap_or_else(|_| usage_error())),
+                ),
+                _ => usage_error(),
+            },
+            "reachability" => match rest {
+                [level, flag, from] if flag == "--from" => commands::reachability(
+                    &PathBuf::from(level),
+                    parse_pos(from).unwrap_or_else(|| usage_error()),
+                ),
+                _ => usage_error(),
+            },
+            "migrate" => commands::migrate(&if rest.is_empty() {
+                commands::all_level_files()
+            } else {
+                rest.iter().map(PathBuf::from).collect::<Vec<PathBuf>>()
+            }),
+            "run-scenarios" => match rest {
+                [scenario_file] => commands::run_scenarios(&PathBuf::from(scenario_file)),
+                _ => usage_error(),
+            },
+            _ => usage_error(),
+        },
+        None => usage_error(),
+    };
+    report.print(json);
+    if !report.passed {
+        process::exit(1);
+    }
+}
+
+/// The game finds its assets relative to its root directory. When running through cargo, that
+/// would be the directory of this crate, so fall back to the workspace root in that case.
+fn set_root_dir(root: Option<String>) {
+    if let Some(root) = root {
+        env::set_var(ROOT_DIR_ENV_VAR, root);
+    } else if env::var_os(ROOT_DIR_ENV_VAR).is_none() {
+        if let Ok(dir) = application_root_dir() {
+            if !dir.join("assets").is_dir() {
+                if let Some(parent) = dir.parent().filter(|dir| dir.join("assets").is_dir()) {
+                    env::set_var(ROOT_DIR_ENV_VAR, parent);
+                }
+            }
+        }
+    }
+}
+
+/// Parses a position in the form "x,y".
+fn parse_pos(text: &str) -> Option<Pos> {
+    let mut parts = text.split(',').map(|part| part.trim().parse::<i32>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Some(Pos::new(x, y)),
+        _ => None,
+    }
+}
+
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
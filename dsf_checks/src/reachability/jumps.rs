@@ -0,0 +1,152 @@
+use amethyst::ecs::Join;
+use amethyst::prelude::WorldExt;
+use dsf_core::components::{Direction1D, Direction2D, Player, Pos, Steering};
+use dsf_core::levels::{Archetype, Level, TileDefinitions};
+use dsf_core::resources::{InputSnapshot, MovementConfig};
+use dsf_core::simulation::{Simulation, FIXED_SECONDS};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// How many ticks a jump is traced before it is assumed the player will never land.
+const MAX_JUMP_TICKS: u32 = 600;
+
+/// The input the player gives during a jump.
+///
+/// A jump starts in the direction the player is holding. For a short while after the jump
+/// started (the jump_allowance in the MovementConfig), the player can still change its direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Jump {
+    /// The direction held when the jump key was pressed.
+    pub direction: Direction1D,
+    /// Optionally, the tick at which the player starts holding another direction.
+    pub steer: Option<(u32, Direction1D)>,
+}
+
+impl Jump {
+    pub fn new(direction: Direction1D) -> Self {
+        Jump {
+            direction,
+            steer: None,
+        }
+    }
+
+    /// The direction the player is holding during the given tick of the jump.
+    fn direction_at(&self, tick: u32) -> Direction1D {
+        match self.steer {
+            Some((steer_tick, direction)) if tick >= steer_tick => direction,
+            _ => self.direction,
+        }
+    }
+}
+
+/// Lists every distinct jump the player can make: straight up, left and right, as well as every
+/// change of direction that is still allowed after the jump started.
+pub fn possible_jumps(config: &MovementConfig) -> Vec<Jump> {
+    let directions = [
+        Direction1D::Negative,
+        Direction1D::Neutral,
+        Direction1D::Positive,
+    ];
+    let mut jumps = directions
+        .iter()
+        .map(|direction| Jump::new(*direction))
+        .collect::<Vec<Jump>>();
+    for steer_tick in 1..grace_ticks(config) {
+        for direction in directions.iter() {
+            for steer_direction in directions.iter() {
+                if direction != steer_direction && !steer_direction.is_neutral() {
+                    jumps.push(Jump {
+                        direction: *direction,
+                        steer: Some((steer_tick, *steer_direction)),
+                    });
+                }
+            }
+        }
+    }
+    jumps
+}
+
+/// Returns every position a player of the given size, standing on the given position, can land
+/// on by jumping. This is empty if the player cannot jump from there at all, for instance because
+/// they are underneath a ceiling.
+///
+/// The level does not need to contain a player; see trace_jump.
+pub fn jump_landings(
+    level: &Level,
+    tile_defs: &TileDefinitions,
+    config: &MovementConfig,
+    pos: Pos,
+    dimens: Pos,
+) -> BTreeSet<Pos> {
+    possible_jumps(config)
+        .iter()
+        .filter_map(|jump| trace_jump(level, tile_defs, config, pos, dimens, jump))
+        .collect()
+}
+
+/// Performs a single jump in a headless Simulation of the level and returns the position the
+/// player lands on.
+///
+/// Any player in the level is left out. Instead, a probe player of the given size is spawned on
+/// the given position, facing the direction of the jump, and the jump is fed to the game play
+/// systems as input. Returns None if the player cannot jump from the given position, dies during
+/// the jump, or does not land within MAX_JUMP_TICKS.
+pub fn trace_jump(
+    level: &Level,
+    tile_defs: &TileDefinitions,
+    config: &MovementConfig,
+    pos: Pos,
+    dimens: Pos,
+    jump: &Jump,
+) -> Option<Pos> {
+    let mut level = level.clone();
+    level
+        .tiles
+        .retain(|_, key| tile_defs.get(key).archetype != Archetype::Player);
+    let mut simulation = Simulation::new(level, tile_defs.clone(), config.clone());
+    simulation.spawn_player(pos, dimens);
+    face(&mut simulation, jump.direction);
+    for tick in 0..MAX_JUMP_TICKS {
+        // The jump key is held throughout, so the player doesn't jump again after landing.
+        simulation.step(InputSnapshot::new(
+            jump.direction_at(tick).signum(),
+            0.,
+            true,
+        ));
+        if simulation.win_condition().is_dying() {
+            return None;
+        }
+        let steering = simulation.player_steering()?;
+        if tick == 0 && !steering.is_jumping() {
+            return None;
+        } else if steering.is_grounded() || steering.is_climbing() {
+            return Some(steering.pos);
+        }
+    }
+    None
+}
+
+/// Turns the player to face the given direction, so that a jump in that direction does not
+/// start with the turn-around delay. Does nothing if the direction is neutral.
+fn face(simulation: &mut Simulation, direction: Direction1D) {
+    if direction.is_neutral() {
+        return;
+    }
+    let world = simulation.world_mut();
+    let players = world.read_storage::<Player>();
+    let mut steerings = world.write_storage::<Steering>();
+    for (_, steering) in (&players, &mut steerings).join() {
+        steering.facing = Direction2D::from(direction, Direction1D::Neutral);
+    }
+}
+
+/// The number of ticks after the start of a jump during which its direction can still change.
+fn grace_ticks(config: &MovementConfig) -> u32 {
+    let mut time_passed = 0.;
+    let mut ticks = 1;
+    while time_passed + FIXED_SECONDS < config.jump_allowance {
+        time_passed += FIXED_SECONDS;
+        ticks += 1;
+    }
+    ticks
+}
@@ -0,0 +1,78 @@
+use serde::Serialize;
+
+/// The result of running a command. Printed either as human-readable text or as JSON.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub command: String,
+    pub passed: bool,
+    pub entries: Vec<Entry>,
+}
+
+/// The result for a single subject, such as a level file or a scenario.
+#[derive(Debug, Serialize)]
+pub struct Entry {
+    pub subject: String,
+    pub passed: bool,
+    pub messages: Vec<String>,
+    /// Structured results, for consumers of the JSON output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Entry {
+    pub fn new(subject: impl Into<String>, passed: bool) -> Self {
+        Entry {
+            subject: subject.into(),
+            passed,
+            messages: vec![],
+            data: None,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.messages.push(message.into());
+        self
+    }
+
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+impl Report {
+    pub fn new(command: &str, entries: Vec<Entry>) -> Self {
+        Report {
+            command: command.to_string(),
+            passed: entries.iter().all(|entry| entry.passed),
+            entries,
+        }
+    }
+
+    pub fn print(&self, json: bool) {
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(self).expect("Failed to serialise report.")
+            );
+        } else {
+            for entry in &self.entries {
+                println!(
+                    "[{}] {}",
+                    if entry.passed { "PASS" } else { "FAIL" },
+                    entry.subject
+                );
+                for message in &entry.messages {
+                    println!("       {}", message);
+                }
+            }
+            let failed = self.entries.iter().filter(|entry| !entry.passed).count();
+            println!(
+                "{}: {} passed, {} failed.",
+                self.command,
+                self.entries.len() - failed,
+                failed
+            );
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::solver::settle;
+use amethyst::config::ConfigError;
+use dsf_core::components::Pos;
+use dsf_core::levels::{load_tile_definitions, Level, TileDefinitions};
+use dsf_core::resources::{InputSnapshot, MovementConfig};
+use dsf_core::simulation::Simulation;
+use dsf_core::utility::files::get_assets_dir;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The scenarios that prove which jumps the player can and cannot make.
+pub fn jump_scenarios_file() -> PathBuf {
+    get_assets_dir().join("tests/movement/jump_scenarios.ron")
+}
+
+/// A file containing any number of movement scenarios.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioFile {
+    pub scenarios: Vec<Scenario>,
+}
+
+/// A scripted piece of game play with an expected outcome. Scenarios are used to prove that the
+/// player can, or can not, perform certain moves.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    /// The level to play the scenario in, relative to the assets directory.
+    pub level: PathBuf,
+    /// Where the player starts. If this is None, the player starts where the level puts them.
+    pub start: Option<Pos>,
+    /// The input, paired with the number of consecutive ticks it is held. This is the same format
+    /// that replays use.
+    pub inputs: Vec<(u32, InputSnapshot)>,
+    pub expect: Expectation,
+}
+
+/// What should happen in a scenario. All expectations are checked after the scripted input has
+/// run out and the player came to a standstill.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub enum Expectation {
+    /// The player ends up standing at exactly this position.
+    LandsAt(Pos),
+    /// The player's x-coordinate never gets greater than this value.
+    NeverBeyondX(i32),
+    /// The player's x-coordinate never gets smaller than this value.
+    NeverBeforeX(i32),
+    /// The player ends up lower than where they started, or never lands at all.
+    Falls,
+}
+
+impl Default for Expectation {
+    fn default() -> Self {
+        Expectation::Falls
+    }
+}
+
+/// What actually happened in a scenario.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScenarioOutcome {
+    pub name: String,
+    pub passed: bool,
+    /// Whether the player came to a standstill after the scripted input ran out.
+    pub landed: bool,
+    /// Where the player was at the end of the scenario.
+    pub end_pos: Option<Pos>,
+    /// The smallest and greatest x-coordinate the player had during the scenario.
+    pub x_range: Option<(i32, i32)>,
+}
+
+impl Scenario {
+    /// The absolute path to the level file.
+    pub fn level_path(&self) -> PathBuf {
+        if self.level.is_absolute() {
+            self.level.clone()
+        } else {
+            get_assets_dir().join(&self.level)
+        }
+    }
+
+    /// Plays this scenario in a headless simulation and checks the result against the
+    /// expectation.
+    pub fn run(
+        &self,
+        tile_defs: &TileDefinitions,
+        config: &MovementConfig,
+    ) -> Result<ScenarioOutcome, ConfigError> {
+        let level = Level::load_migrated(self.level_path())?;
+        let mut simulation = Simulation::new(level, tile_defs.clone(), config.clone());
+        if let Some(start) = self.start {
+            simulation.place_player(start);
+        }
+        let start_y = simulation.player_steering().map(|steering| steering.pos.y);
+        let mut x_range: Option<(i32, i32)> = None;
+        let mut track = |simulation: &Simulation| {
+            if let Some(steering) = simulation.player_steering() {
+                let x = steering.pos.x;
+                x_range = Some(x_range.map_or((x, x), |(min, max)| (min.min(x), max.max(x))));
+            }
+        };
+        track(&simulation);
+        for (ticks, input) in &self.inputs {
+            for _ in 0..*ticks {
+                simulation.step(*input);
+                track(&simulation);
+            }
+        }
+        let landed = settle(&mut simulation);
+        track(&simulation);
+        let end_pos = simulation.player_steering().map(|steering| steering.pos);
+        let passed = match &self.expect {
+            Expectation::LandsAt(pos) => landed && end_pos == Some(*pos),
+            Expectation::NeverBeyondX(max_x) => x_range.map_or(false, |(_, max)| max <= *max_x),
+            Expectation::NeverBeforeX(min_x) => x_range.map_or(false, |(min, _)| min >= *min_x),
+            Expectation::Falls => {
+                !landed
+                    || match (start_y, end_pos) {
+                        (Some(start_y), Some(end_pos)) => end_pos.y < start_y,
+                        _ => false,
+                    }
+            }
+        };
+        Ok(ScenarioOutcome {
+            name: self.name.clone(),
+            passed,
+            landed,
+            end_pos,
+            x_range,
+        })
+    }
+}
+
+impl ScenarioFile {
+    /// Runs every scenario in this file. Stops at the first scenario whose level fails to load.
+    pub fn run_all(&self, config: &MovementConfig) -> Result<Vec<ScenarioOutcome>, ConfigError> {
+        let tile_defs = load_tile_definitions()?;
+        self.scenarios
+            .iter()
+            .map(|scenario| scenario.run(&tile_defs, config))
+            .collect()
+    }
+}
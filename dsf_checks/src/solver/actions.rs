@@ -0,0 +1,136 @@
+use dsf_core::components::{Direction1D, Steering};
+use dsf_core::resources::InputSnapshot;
+use dsf_core::simulation::Simulation;
+use serde::{Deserialize, Serialize};
+
+/// How many ticks an action may take before the solver gives up on it. An action that takes this
+/// long is most likely an endless fall through a level without a floor.
+const MAX_ACTION_TICKS: u32 = 600;
+
+/// How many ticks a movement key is held at most. If the player didn't start moving by then,
+/// they are most likely up against a wall.
+const MAX_HOLD_TICKS: u32 = 30;
+
+/// A single meaningful thing the player can do while standing still.
+///
+/// Actions are not implemented with rules of their own. Each action is a short script of inputs
+/// that is fed into the real game play systems, so the solver can never disagree with the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Action {
+    /// Walk one tile in the given direction, possibly walking off a ledge.
+    Walk(Direction1D),
+    /// Turn around to face the given direction without walking.
+    Turn(Direction1D),
+    /// Jump in the given direction, or straight up if the direction is neutral.
+    Jump(Direction1D),
+    /// Climb one tile up or down a ladder.
+    Climb(Direction1D),
+    /// Use the equipped tool.
+    UseTool,
+}
+
+impl Action {
+    /// All actions that are worth trying from a state with the given properties.
+    pub fn candidates(facing: Direction1D, has_tool: bool) -> Vec<Action> {
+        let mut actions = vec![
+            Action::Walk(Direction1D::Negative),
+            Action::Walk(Direction1D::Positive),
+            Action::Climb(Direction1D::Negative),
+            Action::Climb(Direction1D::Positive),
+        ];
+        if facing.is_positive() {
+            actions.push(Action::Turn(Direction1D::Negative));
+        } else if facing.is_negative() {
+            actions.push(Action::Turn(Direction1D::Positive));
+        }
+        if has_tool {
+            actions.push(Action::UseTool);
+        } else {
+            actions.push(Action::Jump(Direction1D::Negative));
+            actions.push(Action::Jump(Direction1D::Neutral));
+            actions.push(Action::Jump(Direction1D::Positive));
+        }
+        actions
+    }
+
+    /// Performs this action in the given simulation, then waits until the player comes to a
+    /// standstill. Returns false if the player never came to a standstill.
+    pub fn perform(&self, simulation: &mut Simulation) -> bool {
+        match *self {
+            Action::Walk(direction) => {
+                let input = InputSnapshot::new(direction.signum(), 0., false);
+                hold_until(simulation, input, |start, now| {
+                    start.destination != now.destination || start.mode != now.mode
+                });
+            }
+            Action::Climb(direction) => {
+                let input = InputSnapshot::new(0., direction.signum(), false);
+                hold_until(simulation, input, |start, now| {
+                    start.destination != now.destination || start.mode != now.mode
+                });
+            }
+            Action::Turn(direction) => {
+                simulation.step(InputSnapshot::new(direction.signum(), 0., false));
+            }
+            Action::Jump(direction) => {
+                simulation.step(InputSnapshot::new(direction.signum(), 0., true));
+            }
+            Action::UseTool => {
+                simulation.step(InputSnapshot::new(0., 0., true));
+            }
+        }
+        settle(simulation)
+    }
+}
+
+/// Holds the given input until the condition holds, comparing the player's steering from before
+/// the input was first given with the steering after each tick.
+fn hold_until<F>(simulation: &mut Simulation, input: InputSnapshot, condition: F)
+where
+    F: Fn(&Steering, &Steering) -> bool,
+{
+    let start = match simulation.player_steering() {
+        Some(steering) => steering,
+        None => return,
+    };
+    for _ in 0..MAX_HOLD_TICKS {
+        simulation.step(input);
+        let now = simulation
+            .player_steering()
+            .expect("Player disappeared from the simulation.");
+        if condition(&start, &now) {
+            return;
+        }
+    }
+}
+
+/// Gives no input until the player stands still on the grid, either on the ground or on a ladder.
+/// Returns false if that never happened.
+pub fn settle(simulation: &mut Simulation) -> bool {
+    for _ in 0..MAX_ACTION_TICKS {
+        simulation.step(InputSnapshot::default());
+        if is_settled(simulation) {
+            return true;
+        }
+    }
+    false
+}
+
+fn is_settled(simulation: &Simulation) -> bool {
+    let steering = simulation.player_steering();
+    let player = simulation.player();
+    let translation = simulation.player_translation();
+    match (steering, player, translation) {
+        (Some(steering), Some(player), Some((x, y))) => {
+            let (centered_x, centered_y) = steering.to_centered_coords(steering.pos);
+            (steering.is_grounded() || steering.is_climbing())
+                && steering.pos == steering.destination
+                && (x - centered_x).abs() < f32::EPSILON
+                && (y - centered_y).abs() < f32::EPSILON
+                && !player.pressing_jump
+                && player.jump_grace_timer.is_none()
+                && player.turn_around_timer.is_none()
+        }
+        _ => false,
+    }
+}
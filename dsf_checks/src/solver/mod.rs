@@ -0,0 +1,7 @@
+mod actions;
+mod search;
+mod state;
+
+pub use self::actions::*;
+pub use self::search::*;
+pub use self::state::*;
@@ -0,0 +1,167 @@
+use crate::solver::{settle, unsupported_tiles, Action, PuzzleState};
+use dsf_core::levels::{Level, TileDefinitions};
+use dsf_core::resources::MovementConfig;
+use dsf_core::simulation::Simulation;
+use std::collections::{HashMap, VecDeque};
+
+/// The default maximum number of distinct states the solver will visit before it gives up.
+pub const DEFAULT_MAX_STATES: usize = 100_000;
+
+/// The outcome of an attempt to solve a level.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolverResult {
+    /// The level can be completed. This is a shortest sequence of actions that does so.
+    Solved(Vec<Action>),
+    /// Every reachable state was visited and none of them completes the level.
+    /// This is a proof that the level cannot be solved with the actions the solver knows about.
+    Unsolvable { explored: usize },
+    /// The solver gave up before it visited every reachable state.
+    Inconclusive {
+        explored: usize,
+        reason: InconclusiveReason,
+    },
+}
+
+/// Why the solver could not decide whether a level can be solved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InconclusiveReason {
+    /// The solver visited the maximum number of states it was allowed to.
+    StateLimit,
+    /// The level contains no player, or the player never comes to a standstill after the level
+    /// was loaded. There is no state to start searching from.
+    NoInitialState,
+    /// The level contains these tiles, whose state the solver does not keep track of.
+    UnsupportedTiles(Vec<String>),
+}
+
+impl SolverResult {
+    pub fn is_solved(&self) -> bool {
+        matches!(self, SolverResult::Solved(_))
+    }
+}
+
+/// Searches all discrete states of a level breadth-first, looking for a way to reach the open
+/// exit door.
+///
+/// Every transition is computed by restoring a state in a fresh headless Simulation and then
+/// performing an action in it. That way, the solver uses exactly the same rules as the game.
+///
+/// Mobs, pushable blocks, dynamite, placeable ladders, triggers and coloured keys are not part of
+/// the discrete state. The solver does not search levels that contain them.
+pub struct Solver {
+    level: Level,
+    tile_defs: TileDefinitions,
+    config: MovementConfig,
+    max_states: usize,
+}
+
+impl Solver {
+    pub fn new(level: Level, tile_defs: TileDefinitions, config: MovementConfig) -> Self {
+        Solver {
+            level,
+            tile_defs,
+            config,
+            max_states: DEFAULT_MAX_STATES,
+        }
+    }
+
+    /// Limit the number of states the solver visits before it gives up.
+    pub fn with_max_states(mut self, max_states: usize) -> Self {
+        self.max_states = max_states;
+        self
+    }
+
+    /// Returns the state the player is in right after the level is loaded and the player has
+    /// come to a standstill. Returns None if the level contains no player, or if the player never
+    /// comes to a standstill.
+    pub fn initial_state(&self) -> Option<PuzzleState> {
+        let mut simulation = self.new_simulation();
+        if !settle(&mut simulation) {
+            return None;
+        }
+        PuzzleState::extract(&simulation, &self.level, &self.tile_defs)
+    }
+
+    /// Returns the state that results from performing the given action in the given state.
+    /// Returns None if the player never comes to a standstill after the action.
+    pub fn next_state(&self, state: &PuzzleState, action: Action) -> Option<PuzzleState> {
+        let mut simulation = self.new_simulation();
+        state.restore(&mut simulation);
+        if action.perform(&mut simulation) {
+            PuzzleState::extract(&simulation, &self.level, &self.tile_defs)
+        } else {
+            None
+        }
+    }
+
+    pub fn solve(&self) -> SolverResult {
+        let unsupported = unsupported_tiles(&self.level, &self.tile_defs);
+        if !unsupported.is_empty() {
+            return SolverResult::Inconclusive {
+                explored: 0,
+                reason: InconclusiveReason::UnsupportedTiles(unsupported),
+            };
+        }
+        let initial = match self.initial_state() {
+            Some(state) => state,
+            None => {
+                return SolverResult::Inconclusive {
+                    explored: 0,
+                    reason: InconclusiveReason::NoInitialState,
+                }
+            }
+        };
+        if initial.won {
+            return SolverResult::Solved(vec![]);
+        }
+        let mut parents: HashMap<PuzzleState, Option<(PuzzleState, Action)>> = HashMap::new();
+        parents.insert(initial.clone(), None);
+        let mut queue = VecDeque::new();
+        queue.push_back(initial);
+        while let Some(state) = queue.pop_front() {
+            if parents.len() >= self.max_states {
+                return SolverResult::Inconclusive {
+                    explored: parents.len(),
+                    reason: InconclusiveReason::StateLimit,
+                };
+            }
+            for action in Action::candidates(state.facing, state.equipped.is_some()) {
+                let next = match self.next_state(&state, action) {
+                    Some(next) if next != state && !parents.contains_key(&next) => next,
+                    _ => continue,
+                };
+                parents.insert(next.clone(), Some((state.clone(), action)));
+                if next.won {
+                    return SolverResult::Solved(trace_back(&parents, next));
+                }
+                queue.push_back(next);
+            }
+        }
+        SolverResult::Unsolvable {
+            explored: parents.len(),
+        }
+    }
+
+    fn new_simulation(&self) -> Simulation {
+        Simulation::new(
+            self.level.clone(),
+            self.tile_defs.clone(),
+            self.config.clone(),
+        )
+    }
+}
+
+/// Follows the parent links from the given state back to the initial state and returns the
+/// actions taken along the way, in order.
+fn trace_back(
+    parents: &HashMap<PuzzleState, Option<(PuzzleState, Action)>>,
+    mut state: PuzzleState,
+) -> Vec<Action> {
+    let mut actions = vec![];
+    while let Some(Some((parent, action))) = parents.get(&state) {
+        actions.push(*action);
+        state = parent.clone();
+    }
+    actions.reverse();
+    actions
+}
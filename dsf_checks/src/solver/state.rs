@@ -0,0 +1,192 @@
+use amethyst::{
+    core::transform::Transform,
+    ecs::{Entities, Join, ReadStorage, Write, WriteStorage},
+    prelude::WorldExt,
+};
+use dsf_core::components::*;
+use dsf_core::levels::{Archetype, Level, TileDefinitions, ToolType};
+use dsf_core::resources::{TileMap, WinCondition};
+use dsf_core::simulation::Simulation;
+use dsf_core::systems::rebuild_equipped_tool;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A discrete snapshot of everything in a level that the player is able to change.
+///
+/// The solver only ever looks at the game in between actions, when the player is standing still
+/// on solid ground or on a ladder. At those moments, this struct fully describes the game, as long
+/// as the level contains none of the tiles listed by unsupported_tiles. In particular, the solver
+/// does not model the player dying or being teleported, so levels with hazards or teleporters are
+/// not supported.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct PuzzleState {
+    /// The player's discrete position.
+    pub pos: Pos,
+    /// The direction the player is facing along the x-axis. This decides which blocks a tool hits.
+    pub facing: Direction1D,
+    /// Whether the player is standing on a ladder rather than on solid ground.
+    pub climbing: bool,
+    /// The tool the player is currently carrying.
+    pub equipped: Option<ToolType>,
+    /// Positions of the keys that were not yet collected.
+    pub keys_left: BTreeSet<Pos>,
+    /// Positions of the tools that were not yet picked up.
+    pub tools_left: BTreeSet<Pos>,
+    /// Positions of the breakable blocks that were broken.
+    pub broken_blocks: BTreeSet<Pos>,
+    /// Whether the player reached the open exit door.
+    pub won: bool,
+}
+
+impl PuzzleState {
+    /// Reads the current state from the given simulation.
+    /// Returns None if the level contains no player.
+    pub fn extract(
+        simulation: &Simulation,
+        level: &Level,
+        tile_defs: &TileDefinitions,
+    ) -> Option<Self> {
+        let steering = simulation.player_steering()?;
+        let player = simulation.player()?;
+        let win = simulation.win_condition();
+        let tile_map = simulation.tile_map();
+        let world = simulation.world();
+        let tools = world.read_storage::<Tool>();
+        let blocks = world.read_storage::<Block>();
+        Some(PuzzleState {
+            pos: steering.pos,
+            facing: steering.facing.x,
+            climbing: steering.is_climbing(),
+            equipped: player.equipped,
+            keys_left: win.keys.iter().copied().collect(),
+            tools_left: (&tools, &blocks)
+                .join()
+                .map(|(_, block)| block.pos)
+                .collect(),
+            broken_blocks: breakable_positions(level, tile_defs)
+                .filter(|pos| tile_map.get_tile(pos).is_none())
+                .collect(),
+            won: win.reached_open_door,
+        })
+    }
+
+    /// Puts the given simulation in this state. The simulation must have been freshly created
+    /// from the same level; this only removes things from the level, it cannot add them back.
+    pub fn restore(&self, simulation: &mut Simulation) {
+        let world = simulation.world_mut();
+        let player_entity = world.exec(
+            #[allow(clippy::type_complexity)]
+            |(
+                entities,
+                mut players,
+                mut steerings,
+                mut transforms,
+                keys,
+                tools,
+                blocks,
+                mut win,
+                mut tile_map,
+            ): (
+                Entities,
+                WriteStorage<Player>,
+                WriteStorage<Steering>,
+                WriteStorage<Transform>,
+                ReadStorage<Key>,
+                ReadStorage<Tool>,
+                ReadStorage<Block>,
+                Write<WinCondition>,
+                Write<TileMap>,
+            )| {
+                let mut player_entity = None;
+                for (entity, player, steering, transform) in
+                    (&entities, &mut players, &mut steerings, &mut transforms).join()
+                {
+                    player_entity = Some(entity);
+                    player.equipped = self.equipped;
+                    // Don't let the player be teleported away before they step off the teleporter
+                    // they might be standing on, the same as after a rewind.
+                    player.teleport_cooldown = Some(0.);
+                    steering.pos = self.pos;
+                    steering.destination = self.pos;
+                    steering.facing = Direction2D::from(self.facing, Direction1D::Neutral);
+                    steering.mode = if self.climbing {
+                        SteeringMode::Climbing
+                    } else {
+                        SteeringMode::Grounded
+                    };
+                    let (centered_x, centered_y) = steering.to_centered_coords(self.pos);
+                    transform.set_translation_x(centered_x);
+                    transform.set_translation_y(centered_y);
+                }
+                for (key, entity) in (&keys, &entities).join() {
+                    if !self.keys_left.contains(&key.pos) {
+                        win.set_key_collected(&key.pos);
+                        entities.delete(entity).expect("Failed to delete key.");
+                    }
+                }
+                for (_, block, entity) in (&tools, &blocks, &entities).join() {
+                    if !self.tools_left.contains(&block.pos) {
+                        entities.delete(entity).expect("Failed to delete tool.");
+                    }
+                }
+                for (block, entity, _) in (&blocks, &entities, !&players).join() {
+                    if self.broken_blocks.contains(&block.pos) {
+                        entities.delete(entity).expect("Failed to delete block.");
+                    }
+                }
+                self.broken_blocks.iter().for_each(|pos| {
+                    tile_map.remove_tile(pos);
+                });
+                win.reached_open_door = self.won;
+                player_entity
+            },
+        );
+        world.maintain();
+        if let (Some(player_entity), Some(_)) = (player_entity, self.equipped) {
+            rebuild_equipped_tool(world, player_entity, self.equipped);
+        }
+    }
+}
+
+/// The keys of the tiles in the level whose state is not part of the PuzzleState, sorted and
+/// without duplicates. The solver cannot reach a reliable verdict on a level that has any.
+///
+/// Mobs and pushable blocks move around, triggers toggle other tiles, coloured keys open locks,
+/// and dynamite and ladders leave things behind in the level once they are used. Hazards kill the
+/// player and teleporters move them elsewhere, neither of which the solver models.
+pub fn unsupported_tiles(level: &Level, tile_defs: &TileDefinitions) -> Vec<String> {
+    let mut keys = level
+        .tiles
+        .values()
+        .filter(|key| {
+            matches!(
+                tile_defs.get(key).archetype,
+                Archetype::MobSpawner(_)
+                    | Archetype::Pushable
+                    | Archetype::Trigger(_)
+                    | Archetype::ColouredKey(_)
+                    | Archetype::Lock(_)
+                    | Archetype::Hazard
+                    | Archetype::Teleporter
+                    | Archetype::Tool(ToolType::Dynamite { .. })
+                    | Archetype::Tool(ToolType::PlaceLadder(_))
+            )
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// All positions in the level that hold a breakable block.
+fn breakable_positions<'a>(
+    level: &'a Level,
+    tile_defs: &'a TileDefinitions,
+) -> impl Iterator<Item = Pos> + 'a {
+    level
+        .tiles
+        .iter()
+        .filter(move |(_, key)| tile_defs.get(key).is_breakable())
+        .map(|(pos, _)| *pos)
+}
@@ -83,9 +83,7 @@ impl SimpleState for MovementTestState {
                     InputEvent::KeyPressed {
                         key_code: VirtualKeyCode::Key3,
                         ..
-                    } => {
-                        //TODO:...
-                    }
+                    } => setup_test(MovementTest::JumpScenarios, data.world),
                     _ => (),
                 };
                 Trans::None
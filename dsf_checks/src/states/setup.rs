@@ -1,12 +1,19 @@
 use amethyst::ecs::{prelude::World, Entities, Join, ReadStorage};
 
 use crate::components::*;
+use crate::scenarios::{jump_scenarios_file, ScenarioFile};
+use amethyst::prelude::Config;
 use dsf_core::levels::load_level;
+use dsf_core::resources::load_movement_config;
 use dsf_core::utility::files::get_assets_dir;
 
 pub fn setup_test(test: MovementTest, world: &mut World) {
     clear_previous_test(world);
-    load_level_from_file(&test, world);
+    match test {
+        MovementTest::Jump2Wide => load_level_from_file("gap_2_wide.ron", world),
+        MovementTest::Jump4Wide => load_level_from_file("gap_4_wide.ron", world),
+        MovementTest::JumpScenarios => run_jump_scenarios(),
+    }
 }
 
 fn clear_previous_test(world: &mut World) {
@@ -21,11 +28,22 @@ fn clear_previous_test(world: &mut World) {
     );
 }
 
-fn load_level_from_file(test: &MovementTest, world: &mut World) {
-    let file_name = match test {
-        MovementTest::Jump2Wide => "jump_2_wide.ron",
-        MovementTest::Jump4Wide => "jump_4_wide.ron",
-    };
-    let level_file = get_assets_dir().join("tests/").join(file_name);
+fn load_level_from_file(file_name: &str, world: &mut World) {
+    let level_file = get_assets_dir().join("tests/movement/").join(file_name);
     load_level(&level_file, world).expect("Failed to load level!");
 }
+
+/// Runs the jump scenarios in a headless simulation and logs whether each of them passed.
+fn run_jump_scenarios() {
+    let config = load_movement_config();
+    match ScenarioFile::load(jump_scenarios_file()).and_then(|file| file.run_all(&config)) {
+        Ok(outcomes) => outcomes.iter().for_each(|outcome| {
+            if outcome.passed {
+                info!("Scenario passed: {}", outcome.name);
+            } else {
+                error!("Scenario failed: {} {:?}", outcome.name, outcome);
+            }
+        }),
+        Err(err) => error!("Failed to run jump scenarios: {:?}", err),
+    }
+}
@@ -0,0 +1,107 @@
+//! Helpers shared by the integration tests. Not every test uses every helper.
+#![allow(dead_code)]
+
+use dsf_checks::solver::Solver;
+use dsf_core::components::Pos;
+use dsf_core::levels::{
+    load_tile_definitions, EdgeRule, Level, PropertyValue, Wrapping, CURRENT_FORMAT_VERSION,
+};
+use dsf_core::resources::{load_movement_config, InputSnapshot};
+use dsf_core::simulation::Simulation;
+use dsf_core::utility::files::ROOT_DIR_ENV_VAR;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Points the game at the workspace root, so the assets directory can be found.
+pub fn set_root_dir() {
+    env::set_var(ROOT_DIR_ENV_VAR, workspace_dir());
+}
+
+pub fn workspace_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("The dsf_checks crate should be inside the workspace.")
+        .to_path_buf()
+}
+
+/// Builds levels for tests in code, so each test shows the layout it depends on.
+pub struct LevelBuilder {
+    level: Level,
+}
+
+impl LevelBuilder {
+    /// An empty level with the given bounds. All borders are walls, so nothing wraps around.
+    pub fn new(pos: Pos, dimens: Pos) -> Self {
+        let mut level = Level::default();
+        level.pos = pos;
+        level.dimens = dimens;
+        level.wrapping = Wrapping {
+            x: EdgeRule::Wall,
+            y: EdgeRule::Wall,
+        };
+        // So the level can be written to a file and loaded again.
+        level.metadata.format_version = CURRENT_FORMAT_VERSION;
+        LevelBuilder { level }
+    }
+
+    pub fn tile(mut self, x: i32, y: i32, key: &str) -> Self {
+        self.level.tiles.insert(Pos::new(x, y), key.to_string());
+        self
+    }
+
+    /// A row of 1 by 1 blocks from x_start up to (not including) x_end.
+    pub fn floor(self, x_start: i32, x_end: i32, y: i32) -> Self {
+        (x_start..x_end).fold(self, |builder, x| builder.tile(x, y, "Block1"))
+    }
+
+    pub fn property(mut self, x: i32, y: i32, name: &str, value: PropertyValue) -> Self {
+        self.level
+            .properties
+            .entry(Pos::new(x, y))
+            .or_default()
+            .set(name, value);
+        self
+    }
+
+    /// Decides what happens at the left and right borders of the level.
+    pub fn edge_x(mut self, edge_rule: EdgeRule) -> Self {
+        self.level.wrapping.x = edge_rule;
+        self
+    }
+
+    /// Decides what happens at the top and bottom borders of the level.
+    pub fn edge_y(mut self, edge_rule: EdgeRule) -> Self {
+        self.level.wrapping.y = edge_rule;
+        self
+    }
+
+    /// The player dies if they land after falling more than this many tiles.
+    pub fn max_safe_fall(mut self, max_safe_fall: f32) -> Self {
+        self.level.hazards.max_safe_fall = Some(max_safe_fall);
+        self
+    }
+
+    pub fn level(self) -> Level {
+        self.level
+    }
+
+    pub fn simulation(self) -> Simulation {
+        set_root_dir();
+        let tile_defs = load_tile_definitions().expect("Failed to load tile definitions.");
+        Simulation::new(self.level, tile_defs, load_movement_config())
+    }
+
+    pub fn solver(self) -> Solver {
+        set_root_dir();
+        let tile_defs = load_tile_definitions().expect("Failed to load tile definitions.");
+        Solver::new(self.level, tile_defs, load_movement_config())
+    }
+}
+
+pub fn walk_right() -> InputSnapshot {
+    InputSnapshot::new(1., 0., false)
+}
+
+pub fn stand_still() -> InputSnapshot {
+    InputSnapshot::default()
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use amethyst::prelude::{Builder, WorldExt};
+use common::{stand_still, LevelBuilder};
+use dsf_core::components::{Dynamite, Pos};
+
+/// Blocks in a row, with room above them for dynamite.
+fn row_of_blocks() -> LevelBuilder {
+    LevelBuilder::new(Pos::new(-10, -2), Pos::new(30, 20))
+        .tile(0, 0, "Block1")
+        .tile(1, 0, "Block1")
+        .tile(5, 0, "Block1")
+        .tile(6, 0, "Block1")
+}
+
+/// Lights the given sticks of dynamite, in the given order, and lets them all explode in the same
+/// tick. Returns the positions of the blocks that are left.
+fn explode(dynamite: &[Dynamite]) -> Vec<Pos> {
+    let mut simulation = row_of_blocks().simulation();
+    for dynamite in dynamite {
+        simulation
+            .world_mut()
+            .create_entity()
+            .with(*dynamite)
+            .build();
+    }
+    simulation.step(stand_still());
+    [(0, 0), (1, 0), (5, 0), (6, 0)]
+        .iter()
+        .map(|(x, y)| Pos::new(*x, *y))
+        .filter(|pos| simulation.tile_map().get_tile(pos).is_some())
+        .collect()
+}
+
+/// The dynamite above (1, 0) breaks that block. The arm of the other explosion that reaches left
+/// stops at that same block, as the blast preview showed; it does not reach through the gap that
+/// the first explosion leaves behind, whichever explodes first.
+#[test]
+fn overlapping_explosions_break_what_each_would_break_alone() {
+    let above = Dynamite::new(Pos::new(1, 1), 1, 0);
+    let beside = Dynamite::new(Pos::new(3, 0), 3, 0);
+    let expected = vec![Pos::new(0, 0), Pos::new(6, 0)];
+    assert_eq!(explode(&[above, beside]), expected);
+    assert_eq!(explode(&[beside, above]), expected);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{stand_still, walk_right, LevelBuilder};
+use dsf_core::components::Pos;
+use dsf_core::levels::EdgeRule;
+use dsf_core::resources::DeathCause;
+use dsf_core::simulation::Simulation;
+
+const MAX_SAFE_FALL: f32 = 6.;
+
+/// A level where the player stands on a ledge of the given height and can walk off it to the
+/// right, onto the floor.
+fn ledge(height: i32) -> Simulation {
+    LevelBuilder::new(Pos::new(-10, -2), Pos::new(40, 20))
+        .floor(-10, 30, -1)
+        .floor(-10, 2, height - 1)
+        .tile(0, height, "Player")
+        .max_safe_fall(MAX_SAFE_FALL)
+        .simulation()
+}
+
+fn walk_off_ledge(simulation: &mut Simulation) {
+    (0..300).for_each(|_| simulation.step(walk_right()));
+    let steering = simulation.player_steering().expect("No player.");
+    assert_eq!(steering.pos.y, 0, "The player never reached the floor.");
+}
+
+#[test]
+fn fall_of_exactly_max_safe_fall_is_survived() {
+    let mut simulation = ledge(MAX_SAFE_FALL as i32);
+    walk_off_ledge(&mut simulation);
+    assert!(!simulation.win_condition().is_dying());
+}
+
+#[test]
+fn fall_higher_than_max_safe_fall_kills() {
+    let mut simulation = ledge(MAX_SAFE_FALL as i32 + 1);
+    (0..300).for_each(|_| simulation.step(walk_right()));
+    assert!(simulation.win_condition().is_dying());
+}
+
+/// Rewinding, undoing or resetting the level can put the player back on the ground in the middle
+/// of a fall. That must not count as a landing.
+#[test]
+fn fall_interrupted_by_putting_player_on_ground_is_survived() {
+    let mut simulation = ledge(MAX_SAFE_FALL as i32 * 2);
+    let mut ticks = 0;
+    while !simulation
+        .player_steering()
+        .expect("No player.")
+        .is_falling()
+    {
+        simulation.step(walk_right());
+        ticks += 1;
+        assert!(ticks < 300, "The player never started falling.");
+    }
+    simulation.place_player(Pos::new(10, 0));
+    (0..60).for_each(|_| simulation.step(stand_still()));
+    assert!(!simulation.win_condition().is_dying());
+}
+
+/// Leaving the level across a border that resets the level is a death like any other.
+#[test]
+fn leaving_the_level_kills() {
+    let mut simulation = LevelBuilder::new(Pos::new(-10, -2), Pos::new(20, 10))
+        .floor(-10, 10, -1)
+        .tile(0, 0, "Player")
+        .edge_x(EdgeRule::ResetLevel)
+        .simulation();
+    (0..300).for_each(|_| simulation.step(walk_right()));
+    assert_eq!(
+        simulation.win_condition().killed_by,
+        Some(DeathCause::LeftLevel)
+    );
+}
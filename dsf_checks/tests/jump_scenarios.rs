@@ -0,0 +1,24 @@
+use amethyst::prelude::Config;
+use dsf_checks::scenarios::{jump_scenarios_file, ScenarioFile};
+use dsf_core::resources::load_movement_config;
+use dsf_core::utility::files::ROOT_DIR_ENV_VAR;
+use std::env;
+use std::path::Path;
+
+/// Fails whenever a change to the movement makes an impossible jump possible, or the other way
+/// around.
+#[test]
+fn jump_scenarios_pass() {
+    let workspace_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("The dsf_checks crate should be inside the workspace.");
+    env::set_var(ROOT_DIR_ENV_VAR, workspace_dir);
+    let outcomes = ScenarioFile::load(jump_scenarios_file())
+        .and_then(|file| file.run_all(&load_movement_config()))
+        .expect("Failed to run the jump scenarios.");
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| !outcome.passed)
+        .collect::<Vec<_>>();
+    assert!(failed.is_empty(), "Failed scenarios: {:#?}", failed);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use amethyst::config::Config;
+use common::workspace_dir;
+use dsf_core::components::Pos;
+use dsf_core::levels::{
+    hash_level, migrate_level, HazardSettings, Level, LevelMetadata, Wrapping,
+    CURRENT_FORMAT_VERSION,
+};
+use std::env;
+use std::fs;
+
+/// A level written before the format was versioned: nothing but its bounds and tiles.
+const VERSION_0: &str = r#"(
+    pos: (x: -5, y: -2),
+    dimens: (x: 10, y: 4),
+    tiles: {
+        (x: -5, y: -2): "Block1",
+        (x: -3, y: -1): "Player",
+        (x: 2, y: -1): "Key",
+    },
+)"#;
+
+#[test]
+fn version_0_is_upgraded_to_the_current_version() {
+    let (level, version) = migrate_level(VERSION_0.as_bytes()).expect("Failed to migrate.");
+    assert_eq!(version, 0);
+    assert_eq!(level.pos, Pos::new(-5, -2));
+    assert_eq!(level.dimens, Pos::new(10, 4));
+    assert_eq!(level.tiles.len(), 3);
+    assert_eq!(
+        level.tiles.get(&Pos::new(-3, -1)).map(String::as_str),
+        Some("Player")
+    );
+    assert_eq!(level.wrapping, Wrapping::default());
+    assert_eq!(level.hazards, HazardSettings::default());
+    assert!(level.properties.is_empty());
+    assert_eq!(
+        level.metadata,
+        LevelMetadata {
+            format_version: CURRENT_FORMAT_VERSION,
+            ..LevelMetadata::default()
+        }
+    );
+}
+
+/// Version 0 is frozen: fields that were only added in later versions are not part of it.
+#[test]
+fn version_0_does_not_know_later_fields() {
+    let contents = VERSION_0.replacen("tiles:", "wrapping: (x: Wall), tiles:", 1);
+    assert!(migrate_level(contents.as_bytes()).is_err());
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    let contents = format!(
+        "(metadata: (format_version: {}))",
+        CURRENT_FORMAT_VERSION + 1
+    );
+    assert!(migrate_level(contents.as_bytes()).is_err());
+}
+
+#[test]
+fn levels_in_the_repository_load() {
+    let levels_dir = workspace_dir().join("assets/world/levels");
+    let level_files = fs::read_dir(&levels_dir).expect("Failed to read the levels directory.");
+    for level_file in level_files {
+        let level_file = level_file
+            .expect("Failed to read a directory entry.")
+            .path();
+        let contents = fs::read(&level_file).expect("Failed to read a level file.");
+        let (level, _) = migrate_level(&contents)
+            .unwrap_or_else(|err| panic!("Failed to migrate {:?}: {:?}", level_file, err));
+        assert!(!level.tiles.is_empty(), "{:?} has no tiles.", level_file);
+    }
+}
+
+/// Rewriting a level in the current format must not invalidate its saves, replays and best runs.
+#[test]
+fn migrating_keeps_the_level_hash() {
+    let dir = env::temp_dir().join("dsf_checks_migration_test");
+    fs::create_dir_all(&dir).expect("Failed to create a temporary directory.");
+    let level_file = dir.join("level.ron");
+    fs::write(&level_file, VERSION_0).expect("Failed to write the level.");
+    let hash_before = hash_level(&level_file).expect("Failed to hash the level.");
+
+    let (level, _) = migrate_level(VERSION_0.as_bytes()).expect("Failed to migrate.");
+    level
+        .write(&level_file)
+        .expect("Failed to write the level.");
+    assert_eq!(hash_level(&level_file).ok(), Some(hash_before));
+
+    let mut retitled = Level::load(&level_file).expect("Failed to load the level.");
+    retitled.metadata.title = Some("Renamed".to_string());
+    retitled
+        .write(&level_file)
+        .expect("Failed to write the level.");
+    assert_eq!(hash_level(&level_file).ok(), Some(hash_before));
+
+    let mut changed = retitled;
+    changed.tiles.remove(&Pos::new(-5, -2));
+    changed
+        .write(&level_file)
+        .expect("Failed to write the level.");
+    assert_ne!(hash_level(&level_file).ok(), Some(hash_before));
+    fs::remove_dir_all(&dir).ok();
+}
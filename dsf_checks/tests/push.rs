@@ -0,0 +1,103 @@
+mod common;
+
+use amethyst::ecs::Join;
+use amethyst::prelude::WorldExt;
+use common::{stand_still, walk_right, LevelBuilder};
+use dsf_core::components::{Block, Pos, Pushable};
+use dsf_core::levels::EdgeRule;
+use dsf_core::resources::Triggers;
+use dsf_core::simulation::Simulation;
+
+/// The position of the only pushable block in the level.
+fn block_pos(simulation: &Simulation) -> Pos {
+    let world = simulation.world();
+    let (pushables, blocks) = (
+        world.read_storage::<Pushable>(),
+        world.read_storage::<Block>(),
+    );
+    let block_pos = (&pushables, &blocks)
+        .join()
+        .map(|(_, block)| block.pos)
+        .next();
+    block_pos.expect("There is no pushable block.")
+}
+
+fn tile_key(simulation: &Simulation, x: i32, y: i32) -> Option<String> {
+    simulation
+        .tile_map()
+        .get_tile_def_key(&Pos::new(x, y))
+        .cloned()
+}
+
+#[test]
+fn falling_block_lands_on_the_floor_over_a_key() {
+    let mut simulation = LevelBuilder::new(Pos::new(-10, -2), Pos::new(30, 20))
+        .floor(-10, 20, -1)
+        .tile(-8, 0, "Player")
+        .tile(2, 6, "PushableBlock")
+        .tile(2, 0, "Key")
+        .simulation();
+    (0..60).for_each(|_| simulation.step(stand_still()));
+    assert_eq!(block_pos(&simulation), Pos::new(2, 0));
+    assert_eq!(
+        tile_key(&simulation, 2, 0),
+        Some("PushableBlock".to_string())
+    );
+}
+
+#[test]
+fn covered_key_comes_back_when_the_block_is_pushed_off_it() {
+    let mut simulation = LevelBuilder::new(Pos::new(-10, -2), Pos::new(30, 20))
+        .floor(-10, 20, -1)
+        .tile(0, 0, "Player")
+        .tile(2, 6, "PushableBlock")
+        .tile(2, 0, "Key")
+        .simulation();
+    (0..60).for_each(|_| simulation.step(stand_still()));
+    (0..120).for_each(|_| simulation.step(walk_right()));
+    assert!(block_pos(&simulation).x >= 4);
+    assert!(simulation.win_condition().keys.is_empty());
+}
+
+#[test]
+fn block_on_a_pressure_plate_holds_it_down() {
+    let mut simulation = LevelBuilder::new(Pos::new(-10, -2), Pos::new(30, 20))
+        .floor(-10, 20, -1)
+        .tile(-8, 0, "Player")
+        .tile(2, 6, "PushableBlock")
+        .tile(2, 0, "PressurePlate")
+        .simulation();
+    (0..60).for_each(|_| simulation.step(stand_still()));
+    assert_eq!(block_pos(&simulation), Pos::new(2, 0));
+    assert!(simulation
+        .world()
+        .read_resource::<Triggers>()
+        .fired
+        .contains(&Pos::new(2, 0)));
+}
+
+#[test]
+fn block_wraps_around_when_it_falls_out_of_the_level() {
+    let mut simulation = LevelBuilder::new(Pos::new(-10, -2), Pos::new(30, 20))
+        .edge_y(EdgeRule::Wrap)
+        .floor(-10, 0, -1)
+        .floor(4, 20, -1)
+        .floor(2, 4, 10)
+        .tile(-8, 0, "Player")
+        .tile(2, 0, "PushableBlock")
+        .simulation();
+    (0..120).for_each(|_| simulation.step(stand_still()));
+    assert_eq!(block_pos(&simulation), Pos::new(2, 11));
+}
+
+#[test]
+fn block_stops_at_the_bottom_border_when_it_acts_as_a_wall() {
+    let mut simulation = LevelBuilder::new(Pos::new(-10, -2), Pos::new(30, 20))
+        .floor(-10, 0, -1)
+        .floor(4, 20, -1)
+        .tile(-8, 0, "Player")
+        .tile(2, 0, "PushableBlock")
+        .simulation();
+    (0..60).for_each(|_| simulation.step(stand_still()));
+    assert_eq!(block_pos(&simulation), Pos::new(2, -2));
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::{set_root_dir, LevelBuilder};
+use dsf_checks::reachability::jump_landings;
+use dsf_core::components::Pos;
+use dsf_core::levels::{load_tile_definitions, Level};
+use dsf_core::resources::load_movement_config;
+use std::collections::BTreeSet;
+
+fn landings(level: &Level, from: Pos) -> BTreeSet<Pos> {
+    set_root_dir();
+    let tile_defs = load_tile_definitions().expect("Failed to load tile definitions.");
+    let dimens = tile_defs
+        .player_dimens()
+        .expect("There is no player tile definition.");
+    jump_landings(level, &tile_defs, &load_movement_config(), from, dimens)
+}
+
+/// The levels contain no player; jump_landings brings its own.
+#[test]
+fn jumps_on_a_flat_floor_land_on_both_sides() {
+    let level = LevelBuilder::new(Pos::new(-20, -2), Pos::new(40, 20))
+        .floor(-20, 20, -1)
+        .level();
+    let landings = landings(&level, Pos::new(0, 0));
+    assert!(landings.contains(&Pos::new(0, 0)), "{:?}", landings);
+    assert!(landings.iter().any(|pos| pos.x > 0), "{:?}", landings);
+    assert!(landings.iter().any(|pos| pos.x < 0), "{:?}", landings);
+    assert!(landings.iter().all(|pos| pos.y == 0), "{:?}", landings);
+}
+
+#[test]
+fn player_cannot_jump_underneath_a_ceiling() {
+    let level = LevelBuilder::new(Pos::new(-20, -2), Pos::new(40, 20))
+        .floor(-20, 20, -1)
+        .floor(-20, 20, 2)
+        .level();
+    assert!(landings(&level, Pos::new(0, 0)).is_empty());
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use amethyst::prelude::{Config, WorldExt};
+use common::{walk_right, LevelBuilder};
+use dsf_core::components::Pos;
+use dsf_core::levels::{EdgeRule, ToolType};
+use dsf_core::resources::{
+    load_movement_config, InputSnapshot, Replay, ReplayPlayback, ReplayRecorder,
+};
+use dsf_core::simulation::{best_replay_file_for_level, save_if_best_run, Simulation};
+use dsf_core::states::build_play_dispatcher;
+use std::env;
+use std::fs;
+
+/// A corridor that wraps around, with a key and a pickaxe on the way to the door, so a run to the
+/// door depends on more of the game rules than just movement. The player starts near the right
+/// border and has to cross it.
+fn corridor() -> LevelBuilder {
+    LevelBuilder::new(Pos::new(-10, -2), Pos::new(40, 20))
+        .edge_x(EdgeRule::Wrap)
+        .floor(-10, 30, -1)
+        .tile(22, 0, "Player")
+        .tile(-6, 0, "Key")
+        .tile(-1, 0, "ToolPickaxe")
+        .tile(6, 0, "Door")
+}
+
+/// Walks to the door, jumping along the way.
+fn run_to_the_door() -> Vec<InputSnapshot> {
+    let jump = InputSnapshot::new(1., 0., true);
+    let mut inputs = vec![walk_right(); 20];
+    inputs.extend(vec![jump; 10]);
+    inputs.extend(vec![walk_right(); 120]);
+    inputs
+}
+
+/// The player's position after a tick, and whether they completed the level.
+type Outcome = (Option<(f32, f32)>, bool);
+
+fn outcome(simulation: &Simulation) -> Outcome {
+    (
+        simulation.player_translation(),
+        simulation.win_condition().reached_open_door,
+    )
+}
+
+/// Plays the given inputs the way the PlayState does: with its dispatcher, which runs systems in
+/// parallel, and with the input fed in through a ReplayPlayback. Returns the recording of the run
+/// and the outcome of every tick.
+fn play(level: LevelBuilder, inputs: &[InputSnapshot]) -> (Replay, Vec<Outcome>) {
+    let mut simulation = level.simulation();
+    let mut dispatcher = build_play_dispatcher();
+    dispatcher.setup(simulation.world_mut());
+    let mut script = Replay::default();
+    inputs.iter().for_each(|input| script.push(*input));
+    simulation.world_mut().insert(ReplayPlayback::new(&script));
+    simulation.world_mut().insert(ReplayRecorder::default());
+    let outcomes = inputs
+        .iter()
+        .map(|_| {
+            dispatcher.dispatch(simulation.world());
+            simulation.world_mut().maintain();
+            outcome(&simulation)
+        })
+        .collect();
+    assert!(simulation.win_condition().reached_open_door);
+    let replay = simulation
+        .world()
+        .read_resource::<ReplayRecorder>()
+        .replay
+        .clone();
+    (replay, outcomes)
+}
+
+#[test]
+fn simulation_follows_a_run_recorded_in_play() {
+    let (replay, outcomes_in_play) = play(corridor(), &run_to_the_door());
+
+    let mut simulation = corridor().simulation();
+    let outcomes_in_simulation = replay
+        .iter()
+        .map(|input| {
+            simulation.step(input);
+            outcome(&simulation)
+        })
+        .collect::<Vec<Outcome>>();
+    assert_eq!(
+        outcomes_in_simulation,
+        outcomes_in_play[..replay.nr_ticks()].to_vec()
+    );
+    assert!(simulation.win_condition().reached_open_door);
+    assert_eq!(
+        simulation.player().and_then(|player| player.equipped),
+        Some(ToolType::BreakBlocksBelow(2))
+    );
+}
+
+#[test]
+fn run_recorded_in_play_is_kept_as_best_run() {
+    let dir = env::temp_dir().join("dsf_checks_replay_test");
+    fs::create_dir_all(&dir).expect("Failed to create a temporary directory.");
+    let level_file = dir.join("dsf_checks_replay_test.ron");
+    corridor()
+        .level()
+        .write(&level_file)
+        .expect("Failed to write the level.");
+    let (played, _) = play(corridor(), &run_to_the_door());
+    let mut replay = Replay::new(&level_file);
+    replay.inputs = played.inputs;
+    let best_file = best_replay_file_for_level(&level_file);
+    fs::remove_file(&best_file).ok();
+
+    save_if_best_run(&level_file, &replay, load_movement_config());
+    let best = Replay::load(&best_file).expect("The run was not kept as the best run.");
+    assert_eq!(best.nr_ticks(), replay.nr_ticks());
+    fs::remove_file(&best_file).ok();
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use amethyst::ecs::{Join, ReadStorage};
+use common::LevelBuilder;
+use dsf_checks::solver::{InconclusiveReason, SolverResult};
+use dsf_core::components::{EquippedTag, Pos};
+use dsf_core::levels::ToolType;
+
+/// A flat level with the player on the left, the exit door on the right, and the only key at the
+/// given position.
+fn corridor(key: Pos) -> LevelBuilder {
+    LevelBuilder::new(Pos::new(-10, -2), Pos::new(30, 20))
+        .floor(-10, 20, -1)
+        .tile(0, 0, "Player")
+        .tile(key.x, key.y, "Key")
+        .tile(12, 0, "Door")
+}
+
+#[test]
+fn key_on_the_floor_is_solved() {
+    match corridor(Pos::new(6, 0)).solver().solve() {
+        SolverResult::Solved(actions) => assert!(!actions.is_empty()),
+        other => panic!("Expected a solution, got {:?}.", other),
+    }
+}
+
+#[test]
+fn key_out_of_reach_is_unsolvable() {
+    match corridor(Pos::new(6, 12)).solver().solve() {
+        SolverResult::Unsolvable { explored } => assert!(explored > 1),
+        other => panic!("Expected no solution, got {:?}.", other),
+    }
+}
+
+#[test]
+fn level_without_player_is_inconclusive() {
+    let result = LevelBuilder::new(Pos::new(-10, -2), Pos::new(30, 20))
+        .floor(-10, 20, -1)
+        .tile(6, 0, "Key")
+        .tile(12, 0, "Door")
+        .solver()
+        .solve();
+    assert_eq!(
+        result,
+        SolverResult::Inconclusive {
+            explored: 0,
+            reason: InconclusiveReason::NoInitialState,
+        }
+    );
+}
+
+#[test]
+fn level_with_untracked_tiles_is_inconclusive() {
+    let result = corridor(Pos::new(6, 0))
+        .tile(3, 0, "PushableBlock")
+        .solver()
+        .solve();
+    assert_eq!(
+        result,
+        SolverResult::Inconclusive {
+            explored: 0,
+            reason: InconclusiveReason::UnsupportedTiles(vec!["PushableBlock".to_string()]),
+        }
+    );
+}
+
+#[test]
+fn level_with_hazards_or_teleporters_is_inconclusive() {
+    let result = corridor(Pos::new(6, 0))
+        .tile(2, 0, "Spikes")
+        .tile(15, 0, "Teleporter")
+        .solver()
+        .solve();
+    assert_eq!(
+        result,
+        SolverResult::Inconclusive {
+            explored: 0,
+            reason: InconclusiveReason::UnsupportedTiles(vec![
+                "Spikes".to_string(),
+                "Teleporter".to_string()
+            ]),
+        }
+    );
+}
+
+#[test]
+fn restored_tool_is_drawn_on_the_player() {
+    let mut state = corridor(Pos::new(6, 0))
+        .solver()
+        .initial_state()
+        .expect("No initial state.");
+    state.equipped = Some(ToolType::BreakBlocksBelow(2));
+    let mut simulation = corridor(Pos::new(6, 0)).simulation();
+    state.restore(&mut simulation);
+    let equipped_tags = simulation.world().system_data::<ReadStorage<EquippedTag>>();
+    assert_eq!((&equipped_tags).join().count(), 1);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{walk_right, LevelBuilder};
+use dsf_core::components::Pos;
+use dsf_core::levels::PropertyValue;
+
+/// The teleport cooldown must run out in fixed time steps, or the player can only teleport once
+/// in a headless simulation.
+#[test]
+fn player_teleports_twice() {
+    let mut simulation = LevelBuilder::new(Pos::new(-10, -2), Pos::new(100, 10))
+        .floor(-10, 90, -1)
+        .tile(0, 0, "Player")
+        .tile(6, 0, "Teleporter")
+        .property(6, 0, "partner", PropertyValue::Pos(Pos::new(14, 0)))
+        .tile(14, 0, "Teleporter")
+        .property(14, 0, "partner", PropertyValue::Pos(Pos::new(6, 0)))
+        .tile(22, 0, "Teleporter")
+        .property(22, 0, "partner", PropertyValue::Pos(Pos::new(40, 0)))
+        .tile(40, 0, "Teleporter")
+        .property(40, 0, "partner", PropertyValue::Pos(Pos::new(22, 0)))
+        .simulation();
+    let mut teleports = 0;
+    let mut last_x = simulation.player_steering().expect("No player.").pos.x;
+    for _ in 0..600 {
+        simulation.step(walk_right());
+        let x = simulation.player_steering().expect("No player.").pos.x;
+        if (x - last_x).abs() > 2 {
+            teleports += 1;
+        }
+        last_x = x;
+    }
+    assert_eq!(teleports, 2, "The player ended up at x={}.", last_x);
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use common::{set_root_dir, LevelBuilder};
+use dsf_core::components::Pos;
+use dsf_core::levels::{
+    load_tile_definitions, KeyColour, Level, LevelViolation, PropertyValue, TriggerEffect,
+    TriggerLink,
+};
+
+fn violations(level: &Level) -> Vec<LevelViolation> {
+    set_root_dir();
+    let tile_defs = load_tile_definitions().expect("Failed to load tile definitions.");
+    level.validate(&tile_defs)
+}
+
+/// A small level that breaks no rules. The tests below each break one rule.
+fn valid() -> LevelBuilder {
+    LevelBuilder::new(Pos::new(0, 0), Pos::new(12, 8))
+        .floor(0, 12, 0)
+        .tile(1, 1, "Player")
+        .tile(4, 1, "Key")
+        .tile(6, 1, "Door")
+}
+
+/// A `links` property that makes the tile at the given position appear.
+fn appear_at(x: i32, y: i32) -> PropertyValue {
+    PropertyValue::Links(vec![TriggerLink {
+        target: Pos::new(x, y),
+        effect: TriggerEffect::Appear,
+    }])
+}
+
+#[test]
+fn valid_level_has_no_violations() {
+    assert_eq!(violations(&valid().level()), vec![]);
+}
+
+#[test]
+fn unknown_tile() {
+    let level = valid().tile(11, 1, "Unobtainium").level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::UnknownTile {
+            pos: Pos::new(11, 1),
+            key: "Unobtainium".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn no_player_blocks() {
+    let mut level = valid().level();
+    level.tiles.remove(&Pos::new(1, 1));
+    let violations = violations(&level);
+    assert_eq!(violations, vec![LevelViolation::NoPlayer]);
+    assert!(violations[0].is_blocking());
+}
+
+#[test]
+fn missing_mandatory_tile() {
+    let mut level = valid().level();
+    level.tiles.remove(&Pos::new(6, 1));
+    let violations = violations(&level);
+    assert_eq!(
+        violations,
+        vec![LevelViolation::MissingMandatoryTile {
+            key: "Door".to_string()
+        }]
+    );
+    assert!(!violations[0].is_blocking());
+}
+
+#[test]
+fn duplicate_unique_tile_blocks() {
+    let level = valid().tile(10, 1, "Player").level();
+    let violations = violations(&level);
+    assert_eq!(
+        violations,
+        vec![LevelViolation::DuplicateUniqueTile {
+            key: "Player".to_string(),
+            positions: vec![Pos::new(1, 1), Pos::new(10, 1)],
+        }]
+    );
+    assert!(violations[0].is_blocking());
+}
+
+#[test]
+fn no_keys() {
+    let mut level = valid().level();
+    level.tiles.remove(&Pos::new(4, 1));
+    assert_eq!(violations(&level), vec![LevelViolation::NoKeys]);
+}
+
+#[test]
+fn overlapping_tiles() {
+    let level = valid().tile(2, 2, "Block1").level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::OverlappingTiles {
+            pos: Pos::new(2, 2),
+            first: Pos::new(1, 1),
+            second: Pos::new(2, 2),
+        }]
+    );
+}
+
+#[test]
+fn invalid_dimensions_block() {
+    let mut level = valid().level();
+    level.dimens = Pos::new(0, 8);
+    let violations = violations(&level);
+    assert_eq!(
+        violations,
+        vec![LevelViolation::InvalidDimensions {
+            dimens: Pos::new(0, 8)
+        }]
+    );
+    assert!(violations[0].is_blocking());
+}
+
+#[test]
+fn out_of_bounds() {
+    let level = valid().tile(11, 7, "Key").level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::OutOfBounds {
+            pos: Pos::new(11, 7),
+            key: "Key".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn links_on_a_tile_that_is_not_a_trigger() {
+    let level = valid()
+        .tile(0, 5, "Block1")
+        .property(4, 1, "links", appear_at(0, 5))
+        .level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::NotATrigger {
+            pos: Pos::new(4, 1)
+        }]
+    );
+}
+
+#[test]
+fn trigger_linked_to_nothing() {
+    let level = valid()
+        .tile(10, 1, "Lever")
+        .property(10, 1, "links", appear_at(0, 5))
+        .level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::MissingTriggerTarget {
+            trigger: Pos::new(10, 1),
+            target: Pos::new(0, 5),
+        }]
+    );
+}
+
+#[test]
+fn lock_without_key() {
+    let level = valid().tile(10, 1, "LockRed").level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::LockWithoutKey {
+            colour: KeyColour::Red
+        }]
+    );
+}
+
+#[test]
+fn duplicate_exit_id() {
+    let level = LevelBuilder::new(Pos::new(0, 0), Pos::new(20, 8))
+        .floor(0, 20, 0)
+        .tile(1, 1, "Player")
+        .tile(4, 1, "Key")
+        .tile(6, 1, "Door")
+        .tile(12, 1, "Door")
+        .level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::DuplicateExitId {
+            id: "main".to_string(),
+            positions: vec![Pos::new(6, 1), Pos::new(12, 1)],
+        }]
+    );
+}
+
+#[test]
+fn unpaired_teleporter() {
+    let level = valid()
+        .tile(1, 5, "Teleporter")
+        .property(1, 5, "partner", PropertyValue::Pos(Pos::new(6, 1)))
+        .level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::UnpairedTeleporter {
+            pos: Pos::new(1, 5)
+        }]
+    );
+}
+
+#[test]
+fn orphaned_properties() {
+    let level = valid()
+        .property(0, 7, "id", PropertyValue::Id("left".to_string()))
+        .level();
+    assert_eq!(
+        violations(&level),
+        vec![LevelViolation::OrphanedProperties {
+            pos: Pos::new(0, 7)
+        }]
+    );
+}
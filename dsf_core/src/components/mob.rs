@@ -0,0 +1,44 @@
+use amethyst::{
+    assets::PrefabData,
+    derive::PrefabData,
+    ecs::{prelude::Entity, Component, WriteStorage},
+    error::Error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Direction1D, Pos};
+use crate::levels::{ContactRule, MobBehaviour, MobDefinition};
+use amethyst::core::ecs::HashMapStorage;
+
+/// The entity with this component is a mob. Its movement is decided by the MobSystem, in the same
+/// way that the player's movement is decided by the PlayerSystem.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData, PartialEq)]
+#[prefab(Component)]
+#[serde(deny_unknown_fields)]
+pub struct Mob {
+    pub behaviour: MobBehaviour,
+    pub on_contact: ContactRule,
+    /// The direction the mob walks in. It turns around when it bumps into a wall.
+    pub walk: Direction1D,
+    /// The direction the mob climbs in. It turns around when it reaches the end of a ladder.
+    pub climb: Direction1D,
+    /// The position where the mob last stepped off a ladder. The mob will not climb again until it
+    /// has moved away from that position, otherwise it would keep climbing the same ladder.
+    pub left_ladder_at: Option<Pos>,
+}
+
+impl Component for Mob {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl Mob {
+    pub fn new(mob_def: &MobDefinition) -> Self {
+        Mob {
+            behaviour: mob_def.behaviour,
+            on_contact: mob_def.on_contact,
+            walk: Direction1D::Positive,
+            climb: Direction1D::Positive,
+            left_ladder_at: None,
+        }
+    }
+}
@@ -1,11 +1,13 @@
 mod adventure;
 mod camera;
+mod mob;
 mod movement;
 mod objects;
 mod player;
 
 pub use self::adventure::*;
 pub use self::camera::*;
+pub use self::mob::*;
 pub use self::movement::*;
 pub use self::objects::*;
 pub use self::player::*;
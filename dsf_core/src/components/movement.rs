@@ -36,7 +36,9 @@ impl Direction2D {
     }
 }
 
-#[derive(Clone, Copy, Component, Debug, Deserialize, Serialize, PrefabData, PartialEq)]
+#[derive(
+    Clone, Copy, Component, Debug, Deserialize, Serialize, PrefabData, PartialEq, Eq, Hash,
+)]
 #[prefab(Component)]
 #[serde(deny_unknown_fields)]
 pub enum Direction1D {
@@ -80,6 +82,14 @@ impl Direction1D {
             Direction1D::Neutral => 0.,
         }
     }
+    /// Returns the opposite direction. Neutral stays neutral.
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction1D::Positive => Direction1D::Negative,
+            Direction1D::Negative => Direction1D::Positive,
+            Direction1D::Neutral => Direction1D::Neutral,
+        }
+    }
     pub fn signum_i(&self) -> i32 {
         match self {
             Direction1D::Positive => 1,
@@ -144,7 +154,7 @@ impl Pos {
 ///
 /// Any non-particle entity that has movement should have steering.
 /// Examples of entities with steering include the Player, enemies and projectiles.
-#[derive(Clone, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PrefabData, PartialEq)]
 #[prefab(Component)]
 #[serde(deny_unknown_fields)]
 pub struct Steering {
@@ -162,6 +172,11 @@ pub struct Steering {
     pub facing: Direction2D,
     pub destination: Pos,
     pub mode: SteeringMode,
+    /// How many tiles the entity fell, if it landed after a fall during the current tick.
+    /// None on every other tick. Measured from where the fall started, which is the top of the
+    /// jump if the entity jumped.
+    #[serde(default)]
+    pub landed_after_fall: Option<f32>,
 }
 
 impl Component for Steering {
@@ -307,6 +322,7 @@ impl Steering {
             facing: Direction2D::new(1., 0.),
             destination: pos,
             mode: SteeringMode::Grounded,
+            landed_after_fall: None,
         }
     }
 
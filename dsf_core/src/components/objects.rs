@@ -1,5 +1,5 @@
-use crate::components::Pos;
-use crate::levels::ToolType;
+use crate::components::{Pos, Steering};
+use crate::levels::{KeyColour, ToolType, TriggerKind};
 use crate::resources::SpriteType;
 use amethyst::core::ecs::{HashMapStorage, NullStorage, VecStorage};
 use amethyst::{
@@ -59,6 +59,185 @@ impl Component for Block {
     type Storage = VecStorage<Self>;
 }
 
+/// A block that the player can push. Its current position is kept in its Block component.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct Pushable {
+    /// The position of the block when the level was loaded. This identifies the block.
+    pub origin: Pos,
+    /// Width and height of the block.
+    pub dimens: Pos,
+}
+
+impl Component for Pushable {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl Pushable {
+    pub fn new(origin: Pos, dimens: Pos) -> Self {
+        Pushable { origin, dimens }
+    }
+
+    /// The translation of the centre of the block when it is at the given position.
+    pub fn to_centered_coords(&self, pos: Pos) -> (f32, f32) {
+        (
+            pos.x as f32 + self.dimens.x as f32 * 0.5,
+            pos.y as f32 + self.dimens.y as f32 * 0.5,
+        )
+    }
+}
+
+/// A pressure plate, tripwire or lever. Whether it is fired is kept in the Triggers resource.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct Trigger {
+    pub kind: TriggerKind,
+    /// The position of the trigger tile. This identifies the trigger.
+    pub pos: Pos,
+    /// Width and height of the trigger tile.
+    pub dimens: Pos,
+}
+
+impl Component for Trigger {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl Trigger {
+    pub fn new(kind: TriggerKind, pos: Pos, dimens: Pos) -> Self {
+        Trigger { kind, pos, dimens }
+    }
+
+    /// True iff an entity with the given steering overlaps this trigger.
+    pub fn is_overlapped_by(&self, steering: &Steering) -> bool {
+        self.overlaps(steering.pos, steering.dimens)
+    }
+
+    /// True iff the given pushable block, anchored at the given position, overlaps this trigger.
+    pub fn is_covered_by(&self, pushable: &Pushable, pos: Pos) -> bool {
+        self.overlaps(pos, pushable.dimens)
+    }
+
+    fn overlaps(&self, pos: Pos, dimens: Pos) -> bool {
+        pos.x < self.pos.x + self.dimens.x
+            && self.pos.x < pos.x + dimens.x
+            && pos.y < self.pos.y + self.dimens.y
+            && self.pos.y < pos.y + dimens.y
+    }
+}
+
+/// One end of a teleporter pair. The position of the other end is read from the "partner"
+/// property of the tile at this teleporter's position.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct Teleporter {
+    pub pos: Pos,
+    pub dimens: Pos,
+}
+
+impl Component for Teleporter {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl Teleporter {
+    pub fn new(pos: Pos, dimens: Pos) -> Self {
+        Teleporter { pos, dimens }
+    }
+
+    /// Whether the given steering overlaps this teleporter.
+    pub fn is_overlapped_by(&self, steering: &Steering) -> bool {
+        steering.pos.x < self.pos.x + self.dimens.x
+            && self.pos.x < steering.pos.x + steering.dimens.x
+            && steering.pos.y < self.pos.y + self.dimens.y
+            && self.pos.y < steering.pos.y + steering.dimens.y
+    }
+}
+
+/// A stick of dynamite that the player placed. It explodes once its fuse runs out.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData, PartialEq)]
+#[prefab(Component)]
+#[serde(deny_unknown_fields)]
+pub struct Dynamite {
+    /// The position at the centre of the explosion.
+    pub pos: Pos,
+    /// How many tiles each arm of the explosion reaches.
+    pub radius: u8,
+    /// The length of the whole fuse in milliseconds, as given by the tool.
+    pub fuse_ms: u32,
+    /// The time in seconds until the dynamite explodes.
+    pub fuse: f32,
+}
+
+impl Component for Dynamite {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl Dynamite {
+    pub fn new(pos: Pos, radius: u8, fuse_ms: u32) -> Self {
+        Dynamite {
+            pos,
+            radius,
+            fuse_ms,
+            fuse: fuse_ms as f32 / 1000.,
+        }
+    }
+
+    /// The tool that this dynamite was placed with.
+    pub fn tool(&self) -> ToolType {
+        ToolType::Dynamite {
+            radius: self.radius,
+            fuse_ms: self.fuse_ms,
+        }
+    }
+}
+
+/// Marks one of the tiles that will be hit by an explosion. Exists only to show the player what
+/// will happen.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct BlastPreviewTag;
+
+impl Component for BlastPreviewTag {
+    type Storage = NullStorage<Self>;
+}
+
+/// A coloured key. Collecting it opens the locks of the same colour.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct ColouredKey {
+    pub colour: KeyColour,
+    pub pos: Pos,
+}
+
+impl Component for ColouredKey {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl ColouredKey {
+    pub fn new(colour: KeyColour, pos: Pos) -> Self {
+        ColouredKey { colour, pos }
+    }
+}
+
+/// A lock block, which disappears when the key with the same colour is collected.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct Lock {
+    pub colour: KeyColour,
+}
+
+impl Component for Lock {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Shows the player one of the coloured keys they hold.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct HeldKeyDisplay;
+
+impl Component for HeldKeyDisplay {
+    type Storage = NullStorage<Self>;
+}
+
 /// A miniature version of every key is found on the exit door.
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
 #[prefab(Component)]
@@ -78,13 +257,26 @@ impl KeyDisplay {
     }
 }
 
-/// The exit door.
+/// The id of an exit door that has no "id" property of its own.
+pub const MAIN_EXIT: &str = "main";
+
+/// An exit door. A level can have several; which one the player leaves through is reported back
+/// to the adventure, so that secret exits can open up different parts of the map.
+/// The door's id is read from the "id" property of the tile at its position.
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
 #[prefab(Component)]
-pub struct ExitDoor;
+pub struct ExitDoor {
+    pub pos: Pos,
+}
 
 impl Component for ExitDoor {
-    type Storage = NullStorage<Self>;
+    type Storage = HashMapStorage<Self>;
+}
+
+impl ExitDoor {
+    pub fn new(pos: Pos) -> Self {
+        ExitDoor { pos }
+    }
 }
 
 /// The blue background sprite.
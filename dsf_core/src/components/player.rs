@@ -39,12 +39,57 @@ pub struct Player {
     /// problem that tapping RIGHT while facing left will not only turn around, but will also move
     /// 1 tile to the right.
     pub turn_around_timer: Option<f32>,
+    /// How many seconds are left before the player can use a teleporter again.
+    ///
+    /// This value is usually None. When the player is teleported, it is set to the cooldown. Once
+    /// the cooldown has run out and the player no longer stands on any teleporter, it is set back
+    /// to None. This keeps the player from being sent straight back through the partner.
+    pub teleport_cooldown: Option<f32>,
 }
 
 impl Component for Player {
     type Storage = HashMapStorage<Self>;
 }
 
+/// The player is dead and playing their death animation. Once the animation finishes, they
+/// respawn.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct Dying {
+    /// How many seconds have passed since the player died.
+    pub elapsed: f32,
+    /// The scale the player had before they died. The death animation shrinks the player, this
+    /// is used to restore them afterwards.
+    pub scale: (f32, f32, f32),
+}
+
+impl Component for Dying {
+    type Storage = HashMapStorage<Self>;
+}
+
+impl Dying {
+    /// How long the death animation lasts, in seconds.
+    pub const DURATION: f32 = 1.;
+
+    pub fn new(scale: (f32, f32, f32)) -> Self {
+        Dying { elapsed: 0., scale }
+    }
+
+    /// Whether the death animation has finished, meaning the player should respawn.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= Dying::DURATION
+    }
+}
+
+/// The entity with this component shows the ghost of the player's best run.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
+#[prefab(Component)]
+pub struct GhostTag;
+
+impl Component for GhostTag {
+    type Storage = NullStorage<Self>;
+}
+
 /// The entity with this component is a tool equipped by the player.
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PrefabData)]
 #[prefab(Component)]
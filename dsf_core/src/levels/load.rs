@@ -6,16 +6,21 @@ use amethyst::{
     core::{math::Vector3, transform::Transform, Parent},
     ecs::{prelude::World, Entities, Entity, EntityBuilder, Join, ReadStorage},
     prelude::*,
-    renderer::{sprite::SpriteRender, Transparent},
+    renderer::{palette::Srgba, resources::Tint, sprite::SpriteRender, Transparent},
 };
 use dsf_precompile::MyPrefabData;
 
 use crate::components::*;
 
-use crate::levels::{Archetype, DepthLayer, Level, TileDefinition, TileDefinitions};
+use crate::levels::{
+    Archetype, DepthLayer, KeyColour, Level, LevelViolation, MobDefinition, TileDefinition,
+    TileDefinitions,
+};
 use crate::resources::*;
+use crate::systems::apply_triggers;
 
 use crate::utility::files::get_world_dir;
+use std::fmt;
 use std::path::PathBuf;
 
 pub fn load_tile_definitions() -> Result<TileDefinitions, ConfigError> {
@@ -23,78 +28,210 @@ pub fn load_tile_definitions() -> Result<TileDefinitions, ConfigError> {
     TileDefinitions::load(file)
 }
 
-pub fn load_level(level_file: &PathBuf, world: &mut World) -> Result<(), ConfigError> {
-    let mut win_condition = WinCondition::default();
-    let display_debug_frames = world.read_resource::<DebugSettings>().display_debug_frames;
+/// Why a level could not be loaded.
+#[derive(Debug)]
+pub enum LoadLevelError {
+    /// The level file or the tile definitions could not be read.
+    Config(ConfigError),
+    /// The level breaks rules that make it unplayable. Holds only the blocking violations.
+    Invalid(Vec<LevelViolation>),
+}
+
+impl From<ConfigError> for LoadLevelError {
+    fn from(err: ConfigError) -> Self {
+        LoadLevelError::Config(err)
+    }
+}
+
+impl fmt::Display for LoadLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadLevelError::Config(err) => write!(f, "The level could not be read: {}", err),
+            LoadLevelError::Invalid(violations) => {
+                write!(f, "The level cannot be played:")?;
+                violations
+                    .iter()
+                    .try_for_each(|violation| write!(f, " {}", violation))
+            }
+        }
+    }
+}
+
+/// Loads the given level file and builds it.
+///
+/// The level is validated first. If it has blocking violations, it is not built and an error is
+/// returned. Other violations are logged, but the level is built regardless; the game does its
+/// best to play slightly broken levels, rather than refusing them.
+pub fn load_level(level_file: &PathBuf, world: &mut World) -> Result<(), LoadLevelError> {
     let tile_defs = load_tile_definitions()?;
-    let level = Level::load(level_file)?;
-    add_background(world, &level.pos, &level.dimens);
+    let level = Level::load_migrated(level_file)?;
+    let (blocking, other): (Vec<LevelViolation>, Vec<LevelViolation>) = level
+        .validate(&tile_defs)
+        .into_iter()
+        .partition(LevelViolation::is_blocking);
+    other
+        .iter()
+        .for_each(|violation| error!("Invalid level {:?}: {}", level_file, violation));
+    if !blocking.is_empty() {
+        return Err(LoadLevelError::Invalid(blocking));
+    }
+    build_level(level, tile_defs, world);
+    Ok(())
+}
+
+/// Creates all entities and resources needed to play the given level.
+///
+/// If the world does not contain the Assets resource, the level is built headless: all graphical
+/// components (sprites, animations, backgrounds, key displays, debug frames) are left out.
+/// This is what the headless Simulation relies on.
+pub fn build_level(level: Level, tile_defs: TileDefinitions, world: &mut World) {
+    let mut win_condition = WinCondition::default();
+    if world.has_value::<Assets>() {
+        add_background(world, &level.pos, &level.dimens);
+    }
     level.tiles.iter().for_each(|(pos, tile_def_key)| {
         let tile_def = tile_defs.get(tile_def_key);
-        let still_asset = load_still_asset(tile_def, &world.read_resource::<Assets>());
-        let anim_asset = load_anim_asset(tile_def, &world.read_resource::<Assets>());
-        let transform = if let Some(asset) = &tile_def.asset {
-            Some(load_transform(
-                &pos,
-                &tile_def.depth,
-                &tile_def.dimens,
-                asset,
-            ))
-        } else {
-            None
-        };
-        let mut builder = world.create_entity();
-        if let Some(still_asset) = still_asset {
-            builder = builder.with(still_asset);
-        }
-        if let Some(anim_asset) = anim_asset {
-            builder = builder.with(anim_asset);
-        }
-        if let Some(transform) = transform {
-            builder = builder.with(transform);
+        if tile_def.archetype == Archetype::Key {
+            win_condition.add_key(pos);
         }
-        builder = builder.with(Block { pos: *pos });
-        match tile_def.archetype {
-            Archetype::Player => {
-                let _ = build_player(builder, pos, tile_def);
-                if display_debug_frames {
-                    build_frames(world, tile_def);
-                }
-            }
-            Archetype::Key => {
-                win_condition.add_key(pos);
-                builder.with(Key::new(*pos)).build();
-            }
-            Archetype::Tool(tool_type) => {
-                if let Some(AssetType::Still(sprite, sprite_nr)) = tile_def.asset {
-                    builder
-                        .with(Tool::new(tool_type, sprite, sprite_nr))
-                        .build();
-                } else {
-                    error!(
-                        "Tool definition {:?} did not have still asset.",
-                        tile_def_key
-                    );
-                }
-            }
-            Archetype::Door => {
-                builder.with(ExitDoor).build();
-            }
-            _ => {
-                builder.build();
-            }
-        };
+        build_tile(world, pos, tile_def_key, tile_def);
     });
-    add_key_displays_to_door(world, &win_condition);
     world.insert(win_condition);
+    if world.has_value::<Assets>() {
+        add_key_displays_to_door(world);
+    }
+    world.insert(KeyRing::default());
+    world.insert(Triggers::new(level.trigger_links()));
     world.insert(TileMap::new(level, tile_defs));
-    world.insert(History::default());
-    Ok(())
+    let history = world
+        .try_fetch::<DebugSettings>()
+        .map(|settings| History::new(settings.max_rewind_seconds))
+        .unwrap_or_default();
+    world.insert(history);
+    // Tiles that only appear when a trigger fires must be hidden from the start.
+    apply_triggers(world);
+}
+
+/// Creates the entity (or entities) for a single tile. This is used when loading a level, and to
+/// bring back tiles that were removed during play when the game is rewound.
+///
+/// Like build_level, this leaves out all graphical components if the world has no Assets.
+pub fn build_tile(world: &mut World, pos: &Pos, tile_def_key: &str, tile_def: &TileDefinition) {
+    let with_graphics = world.has_value::<Assets>();
+    let display_debug_frames = with_graphics
+        && world
+            .try_fetch::<DebugSettings>()
+            .map(|settings| settings.display_debug_frames)
+            .unwrap_or(false);
+    let (still_asset, anim_asset) = if with_graphics {
+        let assets = world.read_resource::<Assets>();
+        (
+            load_still_asset(tile_def, &assets),
+            load_anim_asset(tile_def, &assets),
+        )
+    } else {
+        (None, None)
+    };
+    let transform = if let Some(asset) = &tile_def.asset {
+        Some(load_transform(
+            &pos,
+            &tile_def.depth,
+            &tile_def.dimens,
+            asset,
+        ))
+    } else {
+        None
+    };
+    let mut builder = world.create_entity();
+    if let Some(still_asset) = still_asset {
+        builder = builder.with(still_asset);
+    }
+    if let Some(anim_asset) = anim_asset {
+        builder = builder.with(anim_asset);
+    }
+    if let Some(transform) = transform {
+        builder = builder.with(transform);
+    }
+    builder = builder.with(Block { pos: *pos });
+    match tile_def.archetype {
+        Archetype::Player => {
+            let _ = build_player(builder, pos, tile_def, with_graphics);
+            if display_debug_frames {
+                build_frames(world, tile_def);
+            }
+        }
+        Archetype::Key => {
+            builder.with(Key::new(*pos)).build();
+        }
+        Archetype::Tool(tool_type) => {
+            if let Some(AssetType::Still(sprite, sprite_nr)) = tile_def.asset {
+                builder
+                    .with(Tool::new(tool_type, sprite, sprite_nr))
+                    .build();
+            } else {
+                error!(
+                    "Tool definition {:?} did not have still asset.",
+                    tile_def_key
+                );
+            }
+        }
+        Archetype::Door => {
+            builder.with(ExitDoor::new(*pos)).build();
+        }
+        Archetype::Pushable => {
+            builder.with(Pushable::new(*pos, tile_def.dimens)).build();
+        }
+        Archetype::MobSpawner(mob_def) => {
+            let _ = build_mob(builder, pos, tile_def, &mob_def, with_graphics);
+        }
+        Archetype::ColouredKey(colour) => {
+            let builder = if with_graphics {
+                builder.with(colour_tint(colour))
+            } else {
+                builder
+            };
+            builder.with(ColouredKey::new(colour, *pos)).build();
+        }
+        Archetype::Lock(colour) => {
+            let builder = if with_graphics {
+                builder.with(colour_tint(colour))
+            } else {
+                builder
+            };
+            builder.with(Lock { colour }).build();
+        }
+        Archetype::Trigger(kind) => {
+            builder
+                .with(Trigger::new(kind, *pos, tile_def.dimens))
+                .build();
+        }
+        Archetype::Teleporter => {
+            builder.with(Teleporter::new(*pos, tile_def.dimens)).build();
+        }
+        _ => {
+            builder.build();
+        }
+    };
+}
+
+/// The tint that gives keys and locks their colour.
+pub fn colour_tint(colour: KeyColour) -> Tint {
+    let (red, green, blue) = colour.rgb();
+    Tint(Srgba::new(red, green, blue, 1.))
 }
 
-fn build_player(builder: EntityBuilder, pos: &Pos, tile_def: &TileDefinition) -> Entity {
+fn build_player(
+    builder: EntityBuilder,
+    pos: &Pos,
+    tile_def: &TileDefinition,
+    with_graphics: bool,
+) -> Entity {
+    let builder = if with_graphics {
+        builder.with(Transparent)
+    } else {
+        builder
+    };
     builder
-        .with(Transparent)
         .with(Velocity::default())
         .with(SteeringIntent::default())
         .with(Steering::new(*pos, tile_def.dimens))
@@ -102,6 +239,26 @@ fn build_player(builder: EntityBuilder, pos: &Pos, tile_def: &TileDefinition) ->
         .build()
 }
 
+fn build_mob(
+    builder: EntityBuilder,
+    pos: &Pos,
+    tile_def: &TileDefinition,
+    mob_def: &MobDefinition,
+    with_graphics: bool,
+) -> Entity {
+    let builder = if with_graphics {
+        builder.with(Transparent)
+    } else {
+        builder
+    };
+    builder
+        .with(Velocity::default())
+        .with(SteeringIntent::default())
+        .with(Steering::new(*pos, tile_def.dimens))
+        .with(Mob::new(mob_def))
+        .build()
+}
+
 pub fn add_background(world: &mut World, pos: &Pos, dimens: &Pos) {
     let transform = load_transform(
         pos,
@@ -118,56 +275,66 @@ pub fn add_background(world: &mut World, pos: &Pos, dimens: &Pos) {
         .build();
 }
 
-fn add_key_displays_to_door(world: &mut World, win_condition: &WinCondition) {
-    let door_entity = world.exec(|(doors, entities): (ReadStorage<ExitDoor>, Entities)| {
+/// Puts a miniature version of every key that is left in the level on each exit door.
+///
+/// Each key always gets the same spot on the door, so the displays can be rebuilt after rewinding
+/// without shuffling them around.
+pub fn add_key_displays_to_door(world: &mut World) {
+    let door_entities = world.exec(|(doors, entities): (ReadStorage<ExitDoor>, Entities)| {
         (&doors, &entities)
             .join()
-            .map(|(_, entity)| (entity))
-            .next()
+            .map(|(_, entity)| entity)
+            .collect::<Vec<_>>()
     });
-    if let Some(door_entity) = door_entity {
+    let keys = {
+        let win_condition = world.read_resource::<WinCondition>();
         win_condition
-            .keys
+            .all_keys
             .iter()
             .enumerate()
-            .for_each(|(index, key)| {
-                // Temporary bit of code to arrange the key displays on the door in a
-                // visually pleasing manner. Rewrite this later, when we know exactly what we
-                // want to do with the door.
-                let i = if index < 2 {
-                    index + 5
-                } else if index < 4 {
-                    index + 7
-                } else if index < 5 {
-                    index
-                } else if index < 7 {
-                    index - 5
-                } else if index < 9 {
-                    index
-                } else if index < 11 {
-                    index - 7
-                } else {
-                    index
-                };
-                let mut transform = Transform::default();
-                let x_offset = i % 4;
-                let y_offset = i / 4;
-                transform.set_translation_x((-1.5 + x_offset as f32) * 64.);
-                transform.set_translation_y((-1.5 + y_offset as f32) * 64.);
-                transform.set_translation_z(1.); //One higher than parent.
-                transform.set_scale(Vector3::new(0.5, 0.5, 1.0));
-                let sprite = load_asset_from_world(&SpriteType::Blocks, 3, world);
-                world
-                    .create_entity()
-                    .with(Parent {
-                        //TODO:FIXME: don't make this a parent, leads to problems.
-                        entity: door_entity,
-                    })
-                    .with(transform)
-                    .with(sprite)
-                    .with(KeyDisplay::new(*key))
-                    .build();
-            });
+            .filter(|(_, key)| win_condition.keys.contains(key))
+            .map(|(index, key)| (index, *key))
+            .collect::<Vec<_>>()
+    };
+    for door_entity in door_entities {
+        keys.iter().for_each(|&(index, key)| {
+            // Temporary bit of code to arrange the key displays on the door in a
+            // visually pleasing manner. Rewrite this later, when we know exactly what we
+            // want to do with the door.
+            let i = if index < 2 {
+                index + 5
+            } else if index < 4 {
+                index + 7
+            } else if index < 5 {
+                index
+            } else if index < 7 {
+                index - 5
+            } else if index < 9 {
+                index
+            } else if index < 11 {
+                index - 7
+            } else {
+                index
+            };
+            let mut transform = Transform::default();
+            let x_offset = i % 4;
+            let y_offset = i / 4;
+            transform.set_translation_x((-1.5 + x_offset as f32) * 64.);
+            transform.set_translation_y((-1.5 + y_offset as f32) * 64.);
+            transform.set_translation_z(1.); //One higher than parent.
+            transform.set_scale(Vector3::new(0.5, 0.5, 1.0));
+            let sprite = load_asset_from_world(&SpriteType::Blocks, 3, world);
+            world
+                .create_entity()
+                .with(Parent {
+                    //TODO:FIXME: don't make this a parent, leads to problems.
+                    entity: door_entity,
+                })
+                .with(transform)
+                .with(sprite)
+                .with(KeyDisplay::new(key))
+                .build();
+        });
     }
 }
 
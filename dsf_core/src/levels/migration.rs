@@ -0,0 +1,107 @@
+use crate::components::Pos;
+use crate::levels::{Level, LevelMetadata, CURRENT_FORMAT_VERSION};
+use crate::utility::files::hash_bytes;
+use amethyst::config::ConfigError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Only reads the format version of a level file, and ignores everything else in it.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct VersionProbe {
+    metadata: MetadataProbe,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct MetadataProbe {
+    format_version: u32,
+}
+
+/// A level file as it was written before the format was versioned.
+///
+/// Old versions of the format are kept here, frozen, so that old level files keep loading no
+/// matter how the Level struct changes. When the format changes: freeze the current Level as
+/// the struct of its version, bump CURRENT_FORMAT_VERSION, and add a step to the chain in
+/// parse_version that upgrades the frozen struct to the new Level.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+struct LevelV0 {
+    pos: Pos,
+    dimens: Pos,
+    tiles: HashMap<Pos, String>,
+}
+
+/// Version 1 added the wrapping rules, hazard settings, tile properties and level metadata. A
+/// version 0 level gets the defaults of each, which is how the game treated it before.
+fn upgrade_v0(level: LevelV0) -> Level {
+    Level {
+        pos: level.pos,
+        dimens: level.dimens,
+        tiles: level.tiles,
+        metadata: LevelMetadata {
+            format_version: 1,
+            ..LevelMetadata::default()
+        },
+        ..Level::default()
+    }
+}
+
+impl Level {
+    /// Loads a level file of any version of the format, upgrading it to the current version if
+    /// it is older. Use this instead of Config::load, which only understands the current version.
+    pub fn load_migrated<P: AsRef<Path>>(level_file: P) -> Result<Level, ConfigError> {
+        let (level, version) = migrate_level(&fs::read(level_file.as_ref())?)?;
+        if version < CURRENT_FORMAT_VERSION {
+            info!(
+                "Level {:?} is in format version {}, upgraded it to version {}.",
+                level_file.as_ref(),
+                version,
+                CURRENT_FORMAT_VERSION
+            );
+        }
+        Ok(level)
+    }
+}
+
+/// Returns a hash of what the level file describes, rather than of its exact contents. Saves,
+/// replays and best runs remember this hash to notice when their level was changed.
+///
+/// The level is upgraded to the current format before it is hashed, and its metadata is left
+/// out. The hash therefore stays the same when the file is rewritten in a newer format, or when
+/// only the title, description and other details of the level are changed.
+pub fn hash_level<P: AsRef<Path>>(level_file: P) -> Result<u64, ConfigError> {
+    let (level, _) = migrate_level(&fs::read(level_file.as_ref())?)?;
+    let level = Level {
+        metadata: LevelMetadata::default(),
+        ..level
+    };
+    let canonical = ron::ser::to_string(&level)?;
+    Ok(hash_bytes(canonical.as_bytes()))
+}
+
+/// Parses the contents of a level file of any version of the format into the current Level.
+/// Returns the level, as well as the version that the file was in.
+pub fn migrate_level(contents: &[u8]) -> Result<(Level, u32), ConfigError> {
+    let version = ron::de::from_bytes::<VersionProbe>(contents)?
+        .metadata
+        .format_version;
+    parse_version(contents, version).map(|level| (level, version))
+}
+
+fn parse_version(contents: &[u8], version: u32) -> Result<Level, ConfigError> {
+    let level = match version {
+        0 => upgrade_v0(ron::de::from_bytes::<LevelV0>(contents)?),
+        CURRENT_FORMAT_VERSION => ron::de::from_bytes::<Level>(contents)?,
+        newer => {
+            return Err(ConfigError::Parser(ron::de::Error::Message(format!(
+            "Level format version {} is newer than version {}, the newest this build understands.",
+            newer, CURRENT_FORMAT_VERSION
+        ))))
+        }
+    };
+    Ok(level)
+}
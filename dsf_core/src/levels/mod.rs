@@ -1,5 +1,11 @@
 mod load;
+mod migration;
+mod properties;
 mod tile_definition;
+mod validation;
 
 pub use self::load::*;
+pub use self::migration::*;
+pub use self::properties::*;
 pub use self::tile_definition::*;
+pub use self::validation::*;
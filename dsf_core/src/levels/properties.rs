@@ -0,0 +1,127 @@
+use crate::components::{Direction2D, Pos};
+use crate::levels::TriggerLink;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Properties of a single tile in a level, on top of those in its tile definition. These allow
+/// two tiles with the same definition to differ: a teleporter can name its partner, a trigger can
+/// name the tiles it toggles, a sign can carry its text, and so on.
+///
+/// Which properties a tile understands depends on its archetype. Unknown properties are ignored.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(transparent)]
+pub struct TileProperties {
+    values: BTreeMap<String, PropertyValue>,
+}
+
+/// The value of a single tile property.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub enum PropertyValue {
+    /// An identifier, for instance to let other tiles refer to this one.
+    Id(String),
+    /// A position in the level, for instance of a linked tile.
+    Pos(Pos),
+    /// Text to show to the player.
+    Text(String),
+    /// A duration in seconds.
+    Seconds(f32),
+    Direction(Direction2D),
+    Int(i32),
+    /// The tiles that a trigger toggles when it fires.
+    Links(Vec<TriggerLink>),
+}
+
+impl TileProperties {
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.values.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, value: PropertyValue) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PropertyValue> {
+        self.values.remove(name)
+    }
+
+    pub fn id(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            PropertyValue::Id(id) => Some(id),
+            other => self.wrong_type(name, "an Id", other),
+        }
+    }
+
+    pub fn pos(&self, name: &str) -> Option<Pos> {
+        match self.get(name)? {
+            PropertyValue::Pos(pos) => Some(*pos),
+            other => self.wrong_type(name, "a Pos", other),
+        }
+    }
+
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            PropertyValue::Text(text) => Some(text),
+            other => self.wrong_type(name, "a Text", other),
+        }
+    }
+
+    pub fn seconds(&self, name: &str) -> Option<f32> {
+        match self.get(name)? {
+            PropertyValue::Seconds(seconds) => Some(*seconds),
+            other => self.wrong_type(name, "a Seconds", other),
+        }
+    }
+
+    pub fn direction(&self, name: &str) -> Option<Direction2D> {
+        match self.get(name)? {
+            PropertyValue::Direction(direction) => Some(*direction),
+            other => self.wrong_type(name, "a Direction", other),
+        }
+    }
+
+    pub fn int(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            PropertyValue::Int(int) => Some(*int),
+            other => self.wrong_type(name, "an Int", other),
+        }
+    }
+
+    pub fn links(&self, name: &str) -> Option<&[TriggerLink]> {
+        match self.get(name)? {
+            PropertyValue::Links(links) => Some(links),
+            other => self.wrong_type(name, "a Links", other),
+        }
+    }
+
+    /// Writes the properties as text, in the same notation as the level file. Used to let the
+    /// level designer edit them.
+    pub fn to_text(&self) -> String {
+        ron::ser::to_string(self).unwrap_or_else(|err| {
+            error!("Failed to write tile properties {:?}: {}", self, err);
+            String::new()
+        })
+    }
+
+    /// Reads properties from text in the same notation as the level file. Empty text means no
+    /// properties.
+    pub fn from_text(text: &str) -> Result<TileProperties, ron::de::Error> {
+        if text.trim().is_empty() {
+            Ok(TileProperties::default())
+        } else {
+            ron::de::from_str(text)
+        }
+    }
+
+    /// Logs that a property has an unexpected type. The property is then treated as missing.
+    fn wrong_type<T>(&self, name: &str, expected: &str, found: &PropertyValue) -> Option<T> {
+        error!(
+            "Tile property {:?} should be {}, but is {:?}. Ignoring it.",
+            name, expected, found
+        );
+        None
+    }
+}
@@ -1,4 +1,5 @@
 use crate::components::Pos;
+use crate::levels::TileProperties;
 use crate::resources::{AssetType, SpriteType};
 use serde::{Deserialize, Serialize, Serializer};
 use std::collections::{BTreeMap, HashMap};
@@ -6,17 +7,142 @@ use std::collections::{BTreeMap, HashMap};
 /// Describes a complete level.
 /// Contains a map of positions, mapped to tile definitions.
 /// This struct can be loaded from a level file and used to start a game.
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct Level {
     pub pos: Pos,
-    /// Width and height of the level. In this game, the world wraps at the borders.
+    /// Width and height of the level. By default, the world wraps at the borders.
     pub dimens: Pos,
+    /// What happens at the borders of the level.
+    pub wrapping: Wrapping,
+    /// What can kill the player, and where they come back after dying.
+    pub hazards: HazardSettings,
     /// Mapping of (x,y) position in the world to a TileDefinition key.
     /// These keys can be used to look up the corresponding TileDefinition.
     #[serde(serialize_with = "ordered_map")]
     pub tiles: HashMap<Pos, String>,
+    /// Properties of individual tiles, keyed by the tile's position. Most tiles have none, and
+    /// are left out.
+    pub properties: BTreeMap<Pos, TileProperties>,
+    /// Information about the level that is shown to the player, but that does not change how the
+    /// level plays.
+    pub metadata: LevelMetadata,
+}
+
+impl Level {
+    /// Wiring between triggers and the tiles they toggle. Maps the position of each tile with a
+    /// `links` property to the links that fire when it does.
+    pub fn trigger_links(&self) -> BTreeMap<Pos, Vec<TriggerLink>> {
+        self.properties
+            .iter()
+            .filter_map(|(pos, properties)| {
+                properties
+                    .links("links")
+                    .map(|links| (*pos, links.to_vec()))
+            })
+            .collect()
+    }
+}
+
+/// Decides what happens at the borders of a level, separately for each axis.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct Wrapping {
+    /// What happens at the left and right borders.
+    pub x: EdgeRule,
+    /// What happens at the top and bottom borders.
+    pub y: EdgeRule,
+}
+
+/// What happens when something reaches a border of the level.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum EdgeRule {
+    /// Anything that leaves the level on one side comes back in on the opposite side.
+    Wrap,
+    /// The border acts as an invulnerable wall, floor or ceiling.
+    Wall,
+    /// If the player leaves the level, the level is reset. Mobs that leave the level wrap around
+    /// as usual.
+    ResetLevel,
+}
+
+impl Default for EdgeRule {
+    fn default() -> Self {
+        EdgeRule::Wrap
+    }
+}
+
+/// Decides what can kill the player in a level, on top of hazard tiles, and what happens next.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct HazardSettings {
+    /// The player dies if they land after falling more than this many tiles. The height is
+    /// measured from where they started falling, which is the top of the jump if they jumped.
+    /// If this is None, falls are always safe.
+    pub max_safe_fall: Option<f32>,
+    pub respawn: Respawn,
+}
+
+/// Where the player comes back after dying.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Respawn {
+    /// The level is reset to its start.
+    LevelStart,
+    /// The game goes back to the state before the player's last action, as if they pressed undo.
+    /// If there is nothing to undo, the level is reset to its start.
+    LastCheckpoint,
+}
+
+impl Default for Respawn {
+    fn default() -> Self {
+        Respawn::LevelStart
+    }
+}
+
+/// The version of the level format that this build writes. Level files without a version were
+/// written before the format was versioned, and are version 0.
+pub const CURRENT_FORMAT_VERSION: u32 = 1;
+
+/// Optional information about a level. Every field may be left out of the level file.
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct LevelMetadata {
+    /// The name shown on the level select screen. If this is None, the file name is shown instead.
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    /// How many seconds an experienced player needs to complete the level.
+    pub par_seconds: Option<f32>,
+    /// How many actions an experienced player needs to complete the level.
+    pub par_moves: Option<u32>,
+    /// File name of the music track to play during this level, for example
+    /// "Loyalty_Freak_Music_-_03_-_Old_Key.mp3". If this is None, the usual shuffled music plays.
+    pub music: Option<String>,
+    /// The version of the level format this file was written in.
+    pub format_version: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    /// Every difficulty, from easiest to hardest.
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
 }
 
 /// A function used by serde to serialise the tile map in a deterministic way.
@@ -35,7 +161,7 @@ where
 /// tile or entity that can be encountered in a level. Level files only contain string references to
 /// tile definitions. This protects level files from becoming outdated when tile definitions are
 /// updated.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct TileDefinitions {
@@ -54,6 +180,47 @@ impl Default for TileDefinitions {
 }
 
 impl<'a> TileDefinitions {
+    /// The still sprite of the tool with the given type. Used to draw a tool that is no longer
+    /// in its original position, such as the tool that the player has equipped.
+    pub fn tool_sprite(&self, tool_type: ToolType) -> Option<(SpriteType, usize)> {
+        let mut tile_defs = self
+            .map
+            .iter()
+            .filter(|(_, tile_def)| tile_def.archetype == Archetype::Tool(tool_type))
+            .collect::<Vec<_>>();
+        tile_defs.sort_by(|(key, _), (other_key, _)| key.cmp(other_key));
+        tile_defs
+            .iter()
+            .find_map(|(_, tile_def)| match tile_def.asset {
+                Some(AssetType::Still(sprite, sprite_nr)) => Some((sprite, sprite_nr)),
+                _ => None,
+            })
+    }
+
+    /// The key of the tile that makes up a placed ladder: a climbable block. Placed ladders are
+    /// built from a stack of these.
+    pub fn ladder_segment(&self) -> Option<&String> {
+        let mut keys = self
+            .map
+            .iter()
+            .filter(|(_, tile_def)| {
+                tile_def.climbable && matches!(tile_def.archetype, Archetype::Block(_))
+            })
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys.first().copied()
+    }
+
+    /// The width and height of the player, as given by the tile definition with the Player
+    /// archetype.
+    pub fn player_dimens(&self) -> Option<Pos> {
+        self.map
+            .values()
+            .find(|tile_def| tile_def.archetype == Archetype::Player)
+            .map(|tile_def| tile_def.dimens)
+    }
+
     pub fn get(&'a self, key: &str) -> &'a TileDefinition {
         self.map
             .get(key)
@@ -98,6 +265,25 @@ pub struct TileDefinition {
 }
 
 impl TileDefinition {
+    /// The tile that lies beyond the borders of a level where the borders act as walls.
+    /// It collides on all sides and cannot be broken.
+    pub fn boundary() -> Self {
+        TileDefinition {
+            depth: DepthLayer::Blocks,
+            dimens: Pos::new(1, 1),
+            unique: false,
+            mandatory: false,
+            climbable: false,
+            collision: Some(CollisionDefinition {
+                collides_top: true,
+                collides_side: true,
+                collides_bottom: true,
+            }),
+            asset: None,
+            archetype: Archetype::Block(Sturdiness::Invulnerable),
+        }
+    }
+
     /// Use the fallback if the real TileDefinition could not be found.
     /// This avoids the game having to panic if a level file is slightly corrupted or out of date.
     pub fn fallback() -> Self {
@@ -199,8 +385,23 @@ pub enum Archetype {
     Key,
     /// After collecting all keys, finish level by reaching this door.
     Door,
-    /// Spawns mobs from this location.
-    MobSpawner,
+    /// A block that the player can push one tile sideways by walking into it. When there is
+    /// nothing beneath it, it falls straight down.
+    Pushable,
+    /// Spawns a mob at this location. The mob definition says how it behaves.
+    MobSpawner(MobDefinition),
+    /// Fires when the player overlaps it, toggling the tiles it is linked to in the level file.
+    Trigger(TriggerKind),
+    /// Kills the player when they overlap it. Examples are spikes and lava.
+    Hazard,
+    /// A coloured key. Collecting it opens all locks of the same colour. Unlike regular keys, it
+    /// is not needed to open the exit door.
+    ColouredKey(KeyColour),
+    /// A lock block. It disappears once the player collects the key with the same colour.
+    Lock(KeyColour),
+    /// One end of a teleporter pair. The player is sent to the other end, given by the tile's
+    /// "partner" property.
+    Teleporter,
     /// A fallback archetype used when an archetype lookup failed.
     NotFound,
     Tool(ToolType),
@@ -212,6 +413,75 @@ impl Default for Archetype {
     }
 }
 
+/// The colour of a coloured key and the locks it opens.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KeyColour {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+}
+
+impl Default for KeyColour {
+    fn default() -> Self {
+        KeyColour::Red
+    }
+}
+
+impl KeyColour {
+    /// The colour used to tint the sprites of keys and locks, as red, green and blue components.
+    pub fn rgb(self) -> (f32, f32, f32) {
+        match self {
+            KeyColour::Red => (1., 0.2, 0.2),
+            KeyColour::Green => (0.2, 1., 0.2),
+            KeyColour::Blue => (0.3, 0.4, 1.),
+            KeyColour::Yellow => (1., 1., 0.2),
+        }
+    }
+}
+
+/// Decides when a trigger fires and for how long.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum TriggerKind {
+    /// Fired for as long as the player stands on it. Its targets toggle back when the player
+    /// steps off.
+    PressurePlate,
+    /// Fires the first time the player passes through it and stays fired.
+    Tripwire,
+    /// Flips between fired and not fired every time the player steps onto it.
+    Lever,
+}
+
+impl Default for TriggerKind {
+    fn default() -> Self {
+        TriggerKind::PressurePlate
+    }
+}
+
+/// Links a trigger to one of the tiles it toggles. A trigger lists its links in its `links` tile
+/// property.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct TriggerLink {
+    /// The position of the targeted tile. This must be the tile's anchor: its bottom-left corner.
+    pub target: Pos,
+    pub effect: TriggerEffect,
+}
+
+/// What happens to a targeted tile when its trigger fires. Each effect is undone when the
+/// trigger stops being fired, and two fired triggers with the same effect on the same tile cancel
+/// each other out.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum TriggerEffect {
+    /// The tile is absent until the trigger fires.
+    Appear,
+    /// The tile disappears when the trigger fires.
+    Disappear,
+    /// Whether the tile collides on its sides is flipped when the trigger fires. This turns a
+    /// wall into a platform that can be walked through, or the other way around.
+    FlipCollidesSide,
+}
+
 /// What it takes to break this block.
 /// This enum has two varieties now (breakable or not breakable) but further nuances could be added later.
 /// For example: more/less resistant to explosions, etc.
@@ -227,7 +497,7 @@ impl Default for Sturdiness {
     }
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(deny_unknown_fields)]
 pub enum ToolType {
     /// This tool will break the blocks that the player is facing, n layers deep.
@@ -242,6 +512,21 @@ pub enum ToolType {
     /// If the player is facing right and occupies blocks (0, 0) to (1, 1) inclusive, the blocks
     /// that are targeted are: (1, -1) to (2, -depth) inclusive.
     BreakBlocksBelow(u8),
+    /// The player places this tool at their feet, in the direction they are facing. Once its fuse
+    /// has burned for the given number of milliseconds, it explodes in a cross pattern with the
+    /// given radius.
+    ///
+    /// Each arm of the cross breaks the first breakable block it reaches and then stops. It also
+    /// stops before any other tile that collides horizontally.
+    Dynamite { radius: u8, fuse_ms: u32 },
+    /// This tool places a ladder in front of the player. The integer argument is the length of
+    /// the ladder, in segments.
+    ///
+    /// If the player stands at the edge of a ledge, facing the drop, the ladder hangs down from
+    /// the edge. Otherwise it stands on the floor in front of the player and reaches upwards.
+    /// The ladder is only placed if every position it would occupy is empty. If it does not fit,
+    /// the player is told so with a sound and keeps the tool.
+    PlaceLadder(u8),
 }
 
 impl Default for ToolType {
@@ -250,6 +535,48 @@ impl Default for ToolType {
     }
 }
 
+/// Describes a kind of mob: how it moves around and what happens when it touches the player.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Hash, Default)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct MobDefinition {
+    pub behaviour: MobBehaviour,
+    pub on_contact: ContactRule,
+}
+
+/// How a mob decides where to move.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum MobBehaviour {
+    /// Walks in a straight line until it bumps into a wall, then turns around.
+    /// It does not avoid ledges; if it walks off one, it falls.
+    Patrol,
+    /// Patrols like the Patrol behaviour, but climbs every ladder it comes across. Once it reaches
+    /// the end of a ladder, it steps off and continues patrolling.
+    Climber,
+}
+
+impl Default for MobBehaviour {
+    fn default() -> Self {
+        MobBehaviour::Patrol
+    }
+}
+
+/// What happens when a mob touches the player. Either way, the player dies: the contact sets
+/// WinCondition::killed_by to DeathCause::Mob with this rule, and the death animation plays.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum ContactRule {
+    /// The level restarts once the death animation is over.
+    ResetLevel,
+    /// The player loses the level. They can no longer move, and must restart the level manually.
+    LoseLevel,
+}
+
+impl Default for ContactRule {
+    fn default() -> Self {
+        ContactRule::ResetLevel
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct CollisionDefinition {
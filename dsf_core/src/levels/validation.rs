@@ -0,0 +1,405 @@
+use crate::components::{Pos, MAIN_EXIT};
+use crate::levels::{Archetype, KeyColour, Level, TileDefinition, TileDefinitions};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// A single way in which a level breaks the rules that every level must follow.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum LevelViolation {
+    /// The level refers to a tile definition that does not exist. The game will use the fallback
+    /// definition (with the NotFound archetype) instead.
+    UnknownTile { pos: Pos, key: String },
+    /// The level contains no player, so there is nobody to play it with.
+    NoPlayer,
+    /// A tile that is marked as mandatory does not appear in the level. The player is left out
+    /// here, a missing player is reported as NoPlayer instead.
+    MissingMandatoryTile { key: String },
+    /// A tile that is marked as unique appears more than once in the level.
+    DuplicateUniqueTile { key: String, positions: Vec<Pos> },
+    /// The level contains no keys, so there is nothing for the player to collect.
+    NoKeys,
+    /// Two tiles claim the same position. Tiles bigger than 1 by 1 claim every position they cover.
+    OverlappingTiles { pos: Pos, first: Pos, second: Pos },
+    /// The level has no width or no height.
+    InvalidDimensions { dimens: Pos },
+    /// (Part of) a tile lies outside the bounds of the level.
+    OutOfBounds { pos: Pos, key: String },
+    /// A tile that is not a trigger has a `links` property.
+    NotATrigger { pos: Pos },
+    /// A trigger is linked to a position that is not the anchor of any tile.
+    MissingTriggerTarget { trigger: Pos, target: Pos },
+    /// The level has properties for a position that is not the anchor of any tile.
+    OrphanedProperties { pos: Pos },
+    /// The level has locks of a colour, but no key of that colour to open them.
+    LockWithoutKey { colour: KeyColour },
+    /// Several exit doors share the same id, so the adventure cannot tell them apart.
+    DuplicateExitId { id: String, positions: Vec<Pos> },
+    /// A teleporter has no partner, or its partner is not a teleporter that points back at it.
+    UnpairedTeleporter { pos: Pos },
+}
+
+impl fmt::Display for LevelViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelViolation::UnknownTile { pos, key } => {
+                write!(f, "Unknown tile {:?} at ({}, {}).", key, pos.x, pos.y)
+            }
+            LevelViolation::NoPlayer => write!(f, "Level must contain a player."),
+            LevelViolation::MissingMandatoryTile { key } => {
+                write!(f, "Level must contain at least one {:?}.", key)
+            }
+            LevelViolation::DuplicateUniqueTile { key, positions } => write!(
+                f,
+                "Level may contain only one {:?}, but found {} at {:?}.",
+                key,
+                positions.len(),
+                positions
+            ),
+            LevelViolation::NoKeys => write!(f, "Level must contain at least one key."),
+            LevelViolation::OverlappingTiles { pos, first, second } => write!(
+                f,
+                "Tiles at ({}, {}) and ({}, {}) overlap at ({}, {}).",
+                first.x, first.y, second.x, second.y, pos.x, pos.y
+            ),
+            LevelViolation::InvalidDimensions { dimens } => write!(
+                f,
+                "Level dimensions ({}, {}) must be positive.",
+                dimens.x, dimens.y
+            ),
+            LevelViolation::NotATrigger { pos } => write!(
+                f,
+                "Tile at ({}, {}) has links, but is not a trigger.",
+                pos.x, pos.y
+            ),
+            LevelViolation::MissingTriggerTarget { trigger, target } => write!(
+                f,
+                "Trigger at ({}, {}) is linked to ({}, {}), but there is no tile there.",
+                trigger.x, trigger.y, target.x, target.y
+            ),
+            LevelViolation::OrphanedProperties { pos } => write!(
+                f,
+                "Level has properties for ({}, {}), but there is no tile there.",
+                pos.x, pos.y
+            ),
+            LevelViolation::LockWithoutKey { colour } => {
+                write!(f, "Level has {:?} locks, but no {:?} key.", colour, colour)
+            }
+            LevelViolation::UnpairedTeleporter { pos } => write!(
+                f,
+                "Teleporter at ({}, {}) is not paired with another teleporter.",
+                pos.x, pos.y
+            ),
+            LevelViolation::DuplicateExitId { id, positions } => write!(
+                f,
+                "Exit id {:?} is used by more than one door: {:?}.",
+                id, positions
+            ),
+            LevelViolation::OutOfBounds { pos, key } => write!(
+                f,
+                "Tile {:?} at ({}, {}) lies outside the level.",
+                key, pos.x, pos.y
+            ),
+        }
+    }
+}
+
+impl LevelViolation {
+    /// Whether this violation makes the level unplayable. The game refuses to load a level with
+    /// blocking violations. Other violations are only reported; the game does its best to play
+    /// slightly broken levels.
+    pub fn is_blocking(&self) -> bool {
+        matches!(
+            self,
+            LevelViolation::NoPlayer
+                | LevelViolation::DuplicateUniqueTile { .. }
+                | LevelViolation::InvalidDimensions { .. }
+        )
+    }
+}
+
+/// A single rule that every level must follow. Returns every violation of the rule in the given
+/// level, in a fixed order.
+type Rule = fn(&Level, &TileDefinitions) -> Vec<LevelViolation>;
+
+/// All rules, in the order in which their violations are listed.
+const RULES: [Rule; 14] = [
+    unknown_tiles,
+    no_player,
+    missing_mandatory_tiles,
+    duplicate_unique_tiles,
+    no_keys,
+    overlapping_tiles,
+    invalid_dimensions,
+    out_of_bounds,
+    links_on_non_triggers,
+    missing_trigger_targets,
+    locks_without_keys,
+    duplicate_exit_ids,
+    unpaired_teleporters,
+    orphaned_properties,
+];
+
+impl Level {
+    /// Checks this level against the rules that every level must follow and returns every
+    /// violation that was found. The level is valid if the returned list is empty.
+    ///
+    /// Violations are listed in a fixed order, so the output is the same every time.
+    pub fn validate(&self, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+        RULES
+            .iter()
+            .flat_map(|rule| rule(self, tile_defs))
+            .collect()
+    }
+}
+
+/// Every tile in the level that has a tile definition, ordered by position.
+fn known_tiles<'a>(
+    level: &'a Level,
+    tile_defs: &'a TileDefinitions,
+) -> Vec<(Pos, &'a String, &'a TileDefinition)> {
+    let tiles: BTreeMap<&Pos, &String> = level.tiles.iter().collect();
+    tiles
+        .into_iter()
+        .filter_map(|(pos, key)| tile_defs.map.get(key).map(|tile_def| (*pos, key, tile_def)))
+        .collect()
+}
+
+/// The positions of the tiles with the given key, ordered by position.
+fn positions_of(level: &Level, tile_defs: &TileDefinitions, key: &str) -> Vec<Pos> {
+    known_tiles(level, tile_defs)
+        .into_iter()
+        .filter(|(_, tile_key, _)| *tile_key == key)
+        .map(|(pos, _, _)| pos)
+        .collect()
+}
+
+/// Tile definition keys, sorted, so that rules that go over them list violations in order.
+fn sorted_keys(tile_defs: &TileDefinitions) -> Vec<&String> {
+    let mut keys = tile_defs.map.keys().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+fn archetype_at<'a>(
+    level: &Level,
+    tile_defs: &'a TileDefinitions,
+    pos: &Pos,
+) -> Option<&'a Archetype> {
+    level
+        .tiles
+        .get(pos)
+        .and_then(|key| tile_defs.map.get(key))
+        .map(|tile_def| &tile_def.archetype)
+}
+
+fn unknown_tiles(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    let tiles: BTreeMap<&Pos, &String> = level.tiles.iter().collect();
+    tiles
+        .into_iter()
+        .filter(|(_, key)| !tile_defs.map.contains_key(*key))
+        .map(|(pos, key)| LevelViolation::UnknownTile {
+            pos: *pos,
+            key: key.clone(),
+        })
+        .collect()
+}
+
+fn no_player(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    let has_player = known_tiles(level, tile_defs)
+        .iter()
+        .any(|(_, _, tile_def)| tile_def.archetype == Archetype::Player);
+    if has_player {
+        vec![]
+    } else {
+        vec![LevelViolation::NoPlayer]
+    }
+}
+
+fn missing_mandatory_tiles(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    sorted_keys(tile_defs)
+        .into_iter()
+        .filter(|key| {
+            let tile_def = &tile_defs.map[*key];
+            tile_def.mandatory && tile_def.archetype != Archetype::Player
+        })
+        .filter(|key| positions_of(level, tile_defs, key).is_empty())
+        .map(|key| LevelViolation::MissingMandatoryTile { key: key.clone() })
+        .collect()
+}
+
+fn duplicate_unique_tiles(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    sorted_keys(tile_defs)
+        .into_iter()
+        .filter(|key| tile_defs.map[*key].unique)
+        .map(|key| (key, positions_of(level, tile_defs, key)))
+        .filter(|(_, positions)| positions.len() > 1)
+        .map(|(key, positions)| LevelViolation::DuplicateUniqueTile {
+            key: key.clone(),
+            positions,
+        })
+        .collect()
+}
+
+fn no_keys(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    let has_keys = known_tiles(level, tile_defs)
+        .iter()
+        .any(|(_, _, tile_def)| tile_def.archetype == Archetype::Key);
+    if has_keys {
+        vec![]
+    } else {
+        vec![LevelViolation::NoKeys]
+    }
+}
+
+fn overlapping_tiles(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    let mut violations = vec![];
+    let mut claimed: BTreeMap<Pos, Pos> = BTreeMap::new();
+    for (pos, _, tile_def) in known_tiles(level, tile_defs) {
+        for x in 0..tile_def.dimens.x {
+            for y in 0..tile_def.dimens.y {
+                let covered = Pos::new(pos.x + x, pos.y + y);
+                if let Some(first) = claimed.insert(covered, pos) {
+                    violations.push(LevelViolation::OverlappingTiles {
+                        pos: covered,
+                        first,
+                        second: pos,
+                    });
+                }
+            }
+        }
+    }
+    violations
+}
+
+fn invalid_dimensions(level: &Level, _: &TileDefinitions) -> Vec<LevelViolation> {
+    if level.dimens.x <= 0 || level.dimens.y <= 0 {
+        vec![LevelViolation::InvalidDimensions {
+            dimens: level.dimens,
+        }]
+    } else {
+        vec![]
+    }
+}
+
+/// Only checked if the level has valid dimensions, otherwise every tile would be out of bounds.
+fn out_of_bounds(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    if level.dimens.x <= 0 || level.dimens.y <= 0 {
+        return vec![];
+    }
+    known_tiles(level, tile_defs)
+        .into_iter()
+        .filter(|(pos, _, tile_def)| {
+            pos.x < level.pos.x
+                || pos.y < level.pos.y
+                || pos.x + tile_def.dimens.x > level.pos.x + level.dimens.x
+                || pos.y + tile_def.dimens.y > level.pos.y + level.dimens.y
+        })
+        .map(|(pos, key, _)| LevelViolation::OutOfBounds {
+            pos,
+            key: key.clone(),
+        })
+        .collect()
+}
+
+fn links_on_non_triggers(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    level
+        .trigger_links()
+        .keys()
+        .filter(|trigger| {
+            !matches!(
+                archetype_at(level, tile_defs, trigger),
+                Some(Archetype::Trigger(_))
+            )
+        })
+        .map(|trigger| LevelViolation::NotATrigger { pos: *trigger })
+        .collect()
+}
+
+fn missing_trigger_targets(level: &Level, _: &TileDefinitions) -> Vec<LevelViolation> {
+    level
+        .trigger_links()
+        .into_iter()
+        .flat_map(|(trigger, links)| {
+            links
+                .into_iter()
+                .filter(|link| !level.tiles.contains_key(&link.target))
+                .map(move |link| LevelViolation::MissingTriggerTarget {
+                    trigger,
+                    target: link.target,
+                })
+        })
+        .collect()
+}
+
+fn locks_without_keys(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    let mut key_colours = BTreeSet::new();
+    let mut lock_colours = BTreeSet::new();
+    for (_, _, tile_def) in known_tiles(level, tile_defs) {
+        match tile_def.archetype {
+            Archetype::ColouredKey(colour) => {
+                key_colours.insert(colour);
+            }
+            Archetype::Lock(colour) => {
+                lock_colours.insert(colour);
+            }
+            _ => (),
+        }
+    }
+    lock_colours
+        .difference(&key_colours)
+        .map(|colour| LevelViolation::LockWithoutKey { colour: *colour })
+        .collect()
+}
+
+fn duplicate_exit_ids(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    let mut exits: BTreeMap<&str, Vec<Pos>> = BTreeMap::new();
+    for (pos, _, tile_def) in known_tiles(level, tile_defs) {
+        if tile_def.archetype == Archetype::Door {
+            let id = level
+                .properties
+                .get(&pos)
+                .and_then(|properties| properties.id("id"))
+                .unwrap_or(MAIN_EXIT);
+            exits.entry(id).or_default().push(pos);
+        }
+    }
+    exits
+        .into_iter()
+        .filter(|(_, positions)| positions.len() > 1)
+        .map(|(id, positions)| LevelViolation::DuplicateExitId {
+            id: id.to_string(),
+            positions,
+        })
+        .collect()
+}
+
+fn unpaired_teleporters(level: &Level, tile_defs: &TileDefinitions) -> Vec<LevelViolation> {
+    let partner = |pos: &Pos| {
+        level
+            .properties
+            .get(pos)
+            .and_then(|properties| properties.pos("partner"))
+    };
+    known_tiles(level, tile_defs)
+        .into_iter()
+        .filter(|(_, _, tile_def)| tile_def.archetype == Archetype::Teleporter)
+        .filter(|(pos, _, _)| match partner(pos) {
+            Some(other) => {
+                archetype_at(level, tile_defs, &other) != Some(&Archetype::Teleporter)
+                    || partner(&other) != Some(*pos)
+            }
+            None => true,
+        })
+        .map(|(pos, _, _)| LevelViolation::UnpairedTeleporter { pos })
+        .collect()
+}
+
+fn orphaned_properties(level: &Level, _: &TileDefinitions) -> Vec<LevelViolation> {
+    level
+        .properties
+        .keys()
+        .filter(|pos| !level.tiles.contains_key(pos))
+        .map(|pos| LevelViolation::OrphanedProperties { pos: *pos })
+        .collect()
+}
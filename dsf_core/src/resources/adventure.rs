@@ -7,7 +7,7 @@ use amethyst::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::components::*;
-use crate::levels::{load_asset_from_world, load_transform, DepthLayer, Level};
+use crate::levels::{load_asset_from_world, load_transform, DepthLayer, Level, LevelMetadata};
 use crate::resources::{AssetType, SpriteType, UserCache};
 use crate::utility::files::{get_adventures_dir, get_levels_dir};
 use amethyst::config::ConfigError;
@@ -26,18 +26,47 @@ impl PositionOnMap {
     }
 }
 
+/// A message for the player on the level select screen, telling them why the level they picked
+/// could not be played. Cleared as soon as they pick something else.
+#[derive(Debug, Default)]
+pub struct LevelSelectMessage {
+    pub text: String,
+}
+
 /// All adventures must start at position (0, 0).
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct Adventure {
     pub(crate) nodes: HashMap<Pos, MapElement>,
+    /// The metadata of every level in the adventure, keyed by level file name. Read from the level
+    /// files when the adventure is loaded.
+    #[serde(skip)]
+    pub(crate) level_metadata: HashMap<String, LevelMetadata>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum MapElement {
+    /// A road that is always open.
     Road,
+    /// A road that only opens once the player has left the given level through the exit with the
+    /// given id. Until then, it is not shown and the cursor cannot move onto it. This is how a
+    /// secret exit can lead to a part of the map that is otherwise out of reach.
+    ExitRoad {
+        level: String,
+        exit: String,
+    },
     Node(AdventureNode),
 }
 
+impl MapElement {
+    /// Whether the player can currently move onto this element of the map.
+    pub fn is_open(&self, user_cache: &UserCache) -> bool {
+        match self {
+            MapElement::ExitRoad { level, exit } => user_cache.has_taken_exit(level, exit),
+            _ => true,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AdventureNode {
     pub name: String,
@@ -72,7 +101,7 @@ pub fn create_default_adventure() {
         .iter()
         .map(|level_name| {
             let level_file = get_levels_dir().join(level_name);
-            (level_name, Level::load(level_file))
+            (level_name, Level::load_migrated(level_file))
         })
         .filter(|(level_name, result)| {
             result
@@ -132,13 +161,36 @@ fn level_files() -> Vec<String> {
 }
 
 pub fn load_adventure(path: &PathBuf, world: &mut World) -> Result<(), ConfigError> {
-    let adventure = Adventure::load(path)?;
+    let mut adventure = Adventure::load(path)?;
+    {
+        let user_cache = world.read_resource::<UserCache>();
+        adventure
+            .nodes
+            .retain(|_, map_element| map_element.is_open(&user_cache));
+    }
     for (pos, map_element) in &adventure.nodes {
         match map_element {
-            MapElement::Road => load_road(pos, world),
+            MapElement::Road | MapElement::ExitRoad { .. } => load_road(pos, world),
             MapElement::Node(node) => load_node(pos, node, world),
         }
     }
+    adventure.level_metadata = adventure
+        .nodes
+        .values()
+        .filter_map(|map_element| match map_element {
+            MapElement::Node(AdventureNode {
+                details: NodeDetails::Level(file_name),
+                ..
+            }) => Some(file_name),
+            _ => None,
+        })
+        .filter_map(|file_name| {
+            Level::load_migrated(get_levels_dir().join(file_name))
+                .map_err(|err| error!("Failed to load level {:?}: {:?}", file_name, err))
+                .ok()
+                .map(|level| (file_name.clone(), level.metadata))
+        })
+        .collect();
     let initial_cursor_pos = {
         let last_known_pos = cursor_position(path, world);
         if adventure.nodes.contains_key(&last_known_pos) {
@@ -99,6 +99,11 @@ pub enum SpriteType {
     Door,
     Selection,
     LevelSelect,
+    Hazards,
+    /// The swirl of a teleporter.
+    Portal,
+    /// Marks a tile that an explosion will hit.
+    BlastPreview,
 }
 
 impl Default for SpriteType {
@@ -124,6 +129,9 @@ pub fn get_asset_dimensions(asset: &AssetType) -> Pos {
             SpriteType::Frame => Pos::new(50, 50),
             SpriteType::Ladder => Pos::new(128, 64),
             SpriteType::Door => Pos::new(256, 256),
+            SpriteType::Hazards => Pos::new(32, 32),
+            SpriteType::Portal => Pos::new(32, 32),
+            SpriteType::BlastPreview => Pos::new(32, 32),
             _ => Pos::new(128, 128),
         },
         AssetType::Animated(anim_type) => match anim_type {
@@ -162,4 +170,8 @@ pub enum SoundType {
     /// Plays when the player resets the puzzle to the beginning
     /// (probably because they made a mistake).
     LvlReset,
+    /// Plays when the player dies, for instance by touching spikes.
+    Death,
+    /// Plays when the player goes through a teleporter.
+    Teleport,
 }
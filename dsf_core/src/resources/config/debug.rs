@@ -1,3 +1,4 @@
+use crate::resources::DEFAULT_MAX_REWIND_SECONDS;
 use crate::utility::files::{get_default_settings_dir, get_user_settings_dir};
 use amethyst::prelude::Config;
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,10 @@ pub struct DebugSettings {
     pub time_scale: f32,
     /// Number of seconds to leave between frames when rewinding time.
     pub seconds_per_rewind_frame: f32,
+    /// How many seconds of play can be rewound at most. Older history is dropped to keep memory
+    /// use in check during long sessions.
+    #[serde(default = "default_max_rewind_seconds")]
+    pub max_rewind_seconds: f32,
     /// Enable this when debugging, to save time when rapidly iterating.
     /// It saves you from having to navigate the menu every time you start the game.
     /// If true, the game will open in the editor state.
@@ -63,6 +68,10 @@ impl DebugSettings {
     }
 }
 
+fn default_max_rewind_seconds() -> f32 {
+    DEFAULT_MAX_REWIND_SECONDS
+}
+
 /// Loads the most relevant instance of DebugSettings.
 ///
 /// If the user DebugSettings file exists, tries to load from user settings first. If that fails,
@@ -1,6 +1,8 @@
+use crate::utility::files::get_config_dir;
+use amethyst::prelude::Config;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct MovementConfig {
@@ -24,3 +26,18 @@ pub struct MovementConfig {
     /// step takes much shorter.
     pub map_cursor_move_low_cooldown: f32,
 }
+
+/// Loads the MovementConfig from the config directory.
+///
+/// If the file fails to load, log an error and fall back to the Default trait implementation
+/// (ie: MovementConfig::default()).
+pub fn load_movement_config() -> MovementConfig {
+    let file = get_config_dir().join("movement.ron");
+    MovementConfig::load(&file).unwrap_or_else(|error| {
+        error!(
+            "Failed to load movement config! Falling back to default. Error: {:?}",
+            error
+        );
+        MovementConfig::default()
+    })
+}
@@ -31,7 +31,13 @@ impl UiHandles {
 
     /// Convenience method that grabs the correct UiHandle and uses it to create an entity.
     /// This is the recommended way to create a GUI.
+    ///
+    /// Does nothing if the world has no UiHandles at all, which is the case in a headless
+    /// simulation.
     pub fn add_ui(key: &UiType, world: &mut World) -> Option<Entity> {
+        if !world.has_value::<UiHandles>() {
+            return None;
+        }
         let handle = world.read_resource::<UiHandles>().clone_handle(key);
         handle.map(|handle| world.create_entity().with(handle).build())
     }
@@ -43,10 +49,19 @@ pub enum UiType {
     Fps,
     /// A message in the center of the screen that tells you that you completed the level.
     WinMessage,
+    /// A message in the center of the screen that tells you that you lost the level.
+    LoseMessage,
     /// Dialog that pops up when you want to save a level in the editor.
     Save,
     /// Ui for the level editor.
     Editor,
+    /// Dialog in the level editor where the title, author and other details of the level are
+    /// edited.
+    LevelDetails,
+    /// Panel in the level editor that lists the rules the level breaks.
+    LevelProblems,
+    /// Dialog in the level editor where the properties of a single tile are edited.
+    TileProperties,
     /// The paused menu. Not currently in use, but will be implemented in the future.
     Paused,
     /// The main menu.
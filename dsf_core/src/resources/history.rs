@@ -1,46 +1,418 @@
 use crate::components::*;
+use crate::levels::{KeyColour, ToolType};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-/// Holds the full history of the current game. Used to rewind games to an earlier point.
+/// How many of the recorded Frames are stored as a delta against the Frame before them, for every
+/// Frame that is stored in full.
+const KEYFRAME_INTERVAL: usize = 32;
+
+/// How many seconds of play can be rewound if the debug settings don't say otherwise.
+pub const DEFAULT_MAX_REWIND_SECONDS: f32 = 300.;
+
+/// Holds the recent history of the current game. Used to rewind games to an earlier point.
 ///
-/// NB: Rewinding is currently considered a debug-only feature. It isn't currently ready for use in
-/// real game play. In the future, it will either be made an official feature or removed altogether.
-#[derive(Debug)]
+/// Only the last few minutes of play are kept, older Frames are dropped. To save memory, most
+/// Frames are stored as the difference with the Frame before them. Every so often, a Frame is
+/// stored in full, so that no Frame takes too long to piece back together.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
 pub struct History {
     /// If this is true, then a new Frame should be created this tick, even if nothing changed.
     /// This is used at the start of the game to create the initial Frame, and also after rewinding,
     /// to record the state of the game at that point.
+    #[serde(skip)]
     pub force_key_frame: bool,
-    /// A stack of Frames. Each frame records some change in game state.
-    frame_stack: Vec<Frame>,
+    /// How many seconds of play can be rewound. Frames that are older are dropped.
+    max_rewind_seconds: f32,
+    /// How many seconds of play have been recorded. Rewinding winds this clock back as well.
+    clock: f32,
+    /// The recorded Frames, oldest first. The oldest stored Frame is always a keyframe.
+    frames: VecDeque<StoredFrame>,
+    /// The most recent Frame, in full.
+    last_frame: Option<Frame>,
+    /// How many Frames were dropped because they were too old.
+    dropped: usize,
+    /// A stack of checkpoints, used to undo the player's last action. Each checkpoint is the state
+    /// of the game right before an action, paired with the number of Frames recorded before it
+    /// (including the dropped Frames).
+    checkpoints: Vec<(usize, Frame)>,
 }
 
 impl Default for History {
     fn default() -> History {
+        History::new(DEFAULT_MAX_REWIND_SECONDS)
+    }
+}
+
+impl History {
+    pub fn new(max_rewind_seconds: f32) -> History {
         History {
             force_key_frame: true,
-            frame_stack: vec![],
+            max_rewind_seconds,
+            clock: 0.,
+            frames: VecDeque::new(),
+            last_frame: None,
+            dropped: 0,
+            checkpoints: vec![],
         }
     }
-}
 
-impl History {
+    /// Advances the clock by the given number of seconds of play.
+    pub fn tick(&mut self, seconds: f32) {
+        self.clock += seconds;
+    }
+
+    /// Records the given Frame. Frames that have become too old to rewind to are dropped, as are
+    /// the checkpoints that were recorded before them.
     pub fn push_frame(&mut self, frame: Frame) {
-        self.frame_stack.push(frame);
+        let frames_since_keyframe = self.frames.iter().rev().position(StoredFrame::is_keyframe);
+        let data = match (&self.last_frame, frames_since_keyframe) {
+            (Some(last_frame), Some(since)) if since + 1 < KEYFRAME_INTERVAL => {
+                FrameData::Delta(Box::new(FrameDelta::between(last_frame, &frame)))
+            }
+            _ => FrameData::Keyframe(Box::new(frame.clone())),
+        };
+        self.frames.push_back(StoredFrame {
+            recorded_at: self.clock,
+            data,
+        });
+        self.last_frame = Some(frame);
+        while self.frames.len() > 1
+            && self
+                .frames
+                .front()
+                .map(|oldest| oldest.recorded_at < self.clock - self.max_rewind_seconds)
+                .unwrap_or(false)
+        {
+            self.drop_oldest();
+        }
+        let dropped = self.dropped;
+        self.checkpoints
+            .retain(|(checkpoint_depth, _)| *checkpoint_depth >= dropped);
     }
 
+    /// Pops the most recent Frame. Checkpoints recorded after that Frame are dropped too, they
+    /// lie in a future that was rewound.
     pub fn pop_frame(&mut self) -> Option<Frame> {
-        self.frame_stack.pop()
+        let popped = self.frames.pop_back()?;
+        let frame = self.last_frame.take();
+        self.clock = popped.recorded_at;
+        self.last_frame = self.rebuild_last_frame();
+        let depth = self.depth();
+        self.checkpoints
+            .retain(|(checkpoint_depth, _)| *checkpoint_depth <= depth);
+        frame
+    }
+
+    /// Whether older Frames were dropped, meaning the game can no longer be rewound all the way
+    /// back to the start of the level.
+    pub fn is_truncated(&self) -> bool {
+        self.dropped > 0
+    }
+
+    /// How many seconds of play can be rewound at most.
+    pub fn max_rewind_seconds(&self) -> f32 {
+        self.max_rewind_seconds
+    }
+
+    pub fn push_checkpoint(&mut self, checkpoint: Frame) {
+        self.checkpoints.push((self.depth(), checkpoint));
+    }
+
+    /// Pops the most recent checkpoint that differs from the current state of the game.
+    /// Checkpoints that are identical to the current state are discarded, undoing to them would
+    /// seem to do nothing.
+    pub fn pop_checkpoint(&mut self) -> Option<Frame> {
+        let current = self.last_frame().cloned();
+        while let Some((_, checkpoint)) = self.checkpoints.pop() {
+            let differs = current
+                .as_ref()
+                .map(|current| current.puzzle_changed_since(&checkpoint))
+                .unwrap_or(true);
+            if differs {
+                return Some(checkpoint);
+            }
+        }
+        None
+    }
+
+    /// The most recently recorded Frame.
+    pub fn last_frame(&self) -> Option<&Frame> {
+        self.last_frame.as_ref()
+    }
+
+    /// The number of Frames recorded so far, including the ones that were dropped.
+    fn depth(&self) -> usize {
+        self.dropped + self.frames.len()
+    }
+
+    /// Drops the oldest Frame. If the Frame after it was stored as a delta, it is turned into a
+    /// keyframe, so that it can still be pieced back together.
+    fn drop_oldest(&mut self) {
+        if let Some(oldest) = self.frames.pop_front() {
+            self.dropped += 1;
+            if let Some(next) = self.frames.front_mut() {
+                if !next.is_keyframe() {
+                    let mut frame = Frame::default();
+                    oldest.data.apply_to(&mut frame);
+                    next.data.apply_to(&mut frame);
+                    next.data = FrameData::Keyframe(Box::new(frame));
+                }
+            }
+        }
+    }
+
+    /// Pieces the most recent stored Frame back together, starting from the last keyframe.
+    fn rebuild_last_frame(&self) -> Option<Frame> {
+        let keyframe = self.frames.iter().rposition(StoredFrame::is_keyframe)?;
+        let mut frame = Frame::default();
+        self.frames
+            .iter()
+            .skip(keyframe)
+            .for_each(|stored| stored.data.apply_to(&mut frame));
+        Some(frame)
+    }
+}
+
+/// A Frame as it is kept in the History.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct StoredFrame {
+    /// The time on the History's clock when the Frame was recorded.
+    recorded_at: f32,
+    data: FrameData,
+}
+
+impl StoredFrame {
+    fn is_keyframe(&self) -> bool {
+        matches!(self.data, FrameData::Keyframe(_))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+enum FrameData {
+    /// The full Frame.
+    Keyframe(Box<Frame>),
+    /// Only what changed since the Frame before it.
+    Delta(Box<FrameDelta>),
+}
+
+impl FrameData {
+    /// Turns the Frame before this one into this one.
+    fn apply_to(&self, frame: &mut Frame) {
+        match self {
+            FrameData::Keyframe(keyframe) => *frame = (**keyframe).clone(),
+            FrameData::Delta(delta) => delta.apply_to(frame),
+        }
+    }
+}
+
+/// The difference between two Frames. Each field is None if it did not change.
+///
+/// Every field of Frame must have a counterpart here, otherwise it would be lost when rewinding.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct FrameDelta {
+    player_steering: Option<Option<Steering>>,
+    player_translation: Option<(f32, f32)>,
+    equipped: Option<Option<ToolType>>,
+    keys: Option<BTreeSet<Pos>>,
+    held_keys: Option<BTreeSet<KeyColour>>,
+    tools: Option<BTreeSet<Pos>>,
+    removed_tiles: Option<BTreeSet<Pos>>,
+    placed_tiles: Option<BTreeMap<Pos, String>>,
+    fired_triggers: Option<BTreeSet<Pos>>,
+    pressed_triggers: Option<BTreeSet<Pos>>,
+    pushables: Option<BTreeSet<(Pos, Pos)>>,
+    mobs: Option<Vec<MobFrame>>,
+    dynamite: Option<Vec<Dynamite>>,
+}
+
+impl FrameDelta {
+    fn between(earlier: &Frame, later: &Frame) -> FrameDelta {
+        fn changed<T: PartialEq + Clone>(earlier: &T, later: &T) -> Option<T> {
+            if earlier == later {
+                None
+            } else {
+                Some(later.clone())
+            }
+        }
+        // Destructured without a rest pattern, so that a field added to Frame does not compile
+        // until it has a counterpart here.
+        let Frame {
+            player_steering,
+            player_translation,
+            equipped,
+            keys,
+            held_keys,
+            tools,
+            removed_tiles,
+            placed_tiles,
+            fired_triggers,
+            pressed_triggers,
+            pushables,
+            mobs,
+            dynamite,
+        } = later;
+        FrameDelta {
+            player_steering: changed(&earlier.player_steering, player_steering),
+            player_translation: changed(&earlier.player_translation, player_translation),
+            equipped: changed(&earlier.equipped, equipped),
+            keys: changed(&earlier.keys, keys),
+            held_keys: changed(&earlier.held_keys, held_keys),
+            tools: changed(&earlier.tools, tools),
+            removed_tiles: changed(&earlier.removed_tiles, removed_tiles),
+            placed_tiles: changed(&earlier.placed_tiles, placed_tiles),
+            fired_triggers: changed(&earlier.fired_triggers, fired_triggers),
+            pressed_triggers: changed(&earlier.pressed_triggers, pressed_triggers),
+            pushables: changed(&earlier.pushables, pushables),
+            mobs: changed(&earlier.mobs, mobs),
+            dynamite: changed(&earlier.dynamite, dynamite),
+        }
+    }
+
+    fn apply_to(&self, frame: &mut Frame) {
+        fn apply<T: Clone>(change: &Option<T>, value: &mut T) {
+            if let Some(change) = change {
+                *value = change.clone();
+            }
+        }
+        apply(&self.player_steering, &mut frame.player_steering);
+        apply(&self.player_translation, &mut frame.player_translation);
+        apply(&self.equipped, &mut frame.equipped);
+        apply(&self.keys, &mut frame.keys);
+        apply(&self.held_keys, &mut frame.held_keys);
+        apply(&self.tools, &mut frame.tools);
+        apply(&self.removed_tiles, &mut frame.removed_tiles);
+        apply(&self.placed_tiles, &mut frame.placed_tiles);
+        apply(&self.fired_triggers, &mut frame.fired_triggers);
+        apply(&self.pressed_triggers, &mut frame.pressed_triggers);
+        apply(&self.pushables, &mut frame.pushables);
+        apply(&self.mobs, &mut frame.mobs);
+        apply(&self.dynamite, &mut frame.dynamite);
     }
 }
 
-#[derive(Debug)]
+/// A snapshot of everything in the game that can change during play. Restoring a Frame puts the
+/// game back in exactly the state it was in when the Frame was recorded.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
 pub struct Frame {
-    pub player_position: Pos,
+    /// The player's steering, including their discrete position, facing and steering mode.
+    /// This is None if the level has no player.
+    pub player_steering: Option<Steering>,
+    /// The player's actual position. May lie between squares, for instance in the middle of a jump.
+    pub player_translation: (f32, f32),
+    /// The tool the player has equipped.
+    pub equipped: Option<ToolType>,
+    /// The positions of the keys that are left in the level.
+    pub keys: BTreeSet<Pos>,
+    /// The colours of the coloured keys that the player holds.
+    pub held_keys: BTreeSet<KeyColour>,
+    /// The positions of the tools that are left in the level.
+    pub tools: BTreeSet<Pos>,
+    /// The positions of the tiles that were removed from the TileMap.
+    pub removed_tiles: BTreeSet<Pos>,
+    /// The tiles that were added during play, such as placed ladders. Maps each anchor to its
+    /// tile definition key.
+    pub placed_tiles: BTreeMap<Pos, String>,
+    /// The positions of the triggers that are fired.
+    pub fired_triggers: BTreeSet<Pos>,
+    /// The positions of the triggers that the player overlaps.
+    pub pressed_triggers: BTreeSet<Pos>,
+    /// The position of every pushable block, paired with the position it had when the level was
+    /// loaded.
+    pub pushables: BTreeSet<(Pos, Pos)>,
+    /// The state of every mob, ordered by the position it spawned at.
+    pub mobs: Vec<MobFrame>,
+    /// Every stick of dynamite that was placed but has not exploded yet, ordered by position.
+    pub dynamite: Vec<Dynamite>,
+}
+
+/// A snapshot of a single mob.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct MobFrame {
+    /// The position the mob spawned at. This identifies the mob.
+    pub spawn: Pos,
+    pub mob: Mob,
+    pub steering: Steering,
+    pub translation: (f32, f32),
 }
 
 impl Frame {
-    pub fn new(player_position: Pos) -> Self {
-        Frame { player_position }
+    /// Whether anything meaningful changed between the given earlier frame and this one.
+    ///
+    /// The exact translations and the progress of jumps and falls are not taken into account,
+    /// otherwise every single tick spent mid-air would be recorded.
+    pub fn has_changed_since(&self, earlier: &Frame) -> bool {
+        let mob_positions = |frame: &Frame| {
+            frame
+                .mobs
+                .iter()
+                .map(|mob_frame| (mob_frame.steering.pos, mob_frame.steering.facing))
+                .collect::<Vec<_>>()
+        };
+        self.puzzle_changed_since(earlier) || mob_positions(self) != mob_positions(earlier)
+    }
+
+    /// Like has_changed_since, but only looks at the player and the level, not at the mobs.
+    pub fn puzzle_changed_since(&self, earlier: &Frame) -> bool {
+        let player_pos = |frame: &Frame| {
+            frame
+                .player_steering
+                .as_ref()
+                .map(|steering| (steering.pos, steering.facing))
+        };
+        player_pos(self) != player_pos(earlier)
+            || self.equipped != earlier.equipped
+            || self.keys != earlier.keys
+            || self.tools != earlier.tools
+            || self.removed_tiles != earlier.removed_tiles
+            || self.placed_tiles != earlier.placed_tiles
+            || self.fired_triggers != earlier.fired_triggers
+            || self.pushables != earlier.pushables
+            || self.dynamite_positions() != earlier.dynamite_positions()
+    }
+
+    /// Whether the player started a meaningful action between the given earlier frame and this
+    /// one: they used or picked up a tool, collected a key, pushed a block, fired a trigger, or
+    /// started a jump.
+    pub fn starts_action_since(&self, earlier: &Frame) -> bool {
+        self.equipped != earlier.equipped
+            || self.keys != earlier.keys
+            || self.tools != earlier.tools
+            || self.removed_tiles != earlier.removed_tiles
+            || self.placed_tiles != earlier.placed_tiles
+            || self.fired_triggers != earlier.fired_triggers
+            || self.pushables != earlier.pushables
+            || (self.is_jumping() && !earlier.is_jumping())
+    }
+
+    /// Whether the player landed between the given earlier frame and this one.
+    pub fn lands_since(&self, earlier: &Frame) -> bool {
+        !self.is_mid_air() && earlier.is_mid_air()
+    }
+
+    /// The fuses are left out on purpose, they burn down every tick.
+    fn dynamite_positions(&self) -> Vec<Pos> {
+        self.dynamite.iter().map(|dynamite| dynamite.pos).collect()
+    }
+
+    fn is_jumping(&self) -> bool {
+        self.player_steering
+            .as_ref()
+            .map(|steering| steering.is_jumping())
+            .unwrap_or(false)
+    }
+
+    fn is_mid_air(&self) -> bool {
+        self.player_steering
+            .as_ref()
+            .map(|steering| steering.is_mid_air())
+            .unwrap_or(false)
     }
 }
 
@@ -63,6 +435,9 @@ impl Default for CurrentState {
 pub struct Rewind {
     /// The time in seconds until a new Frame can be popped off the History.
     pub cooldown: f32,
+    /// Whether the player has been told that they cannot rewind any further. This is reset when
+    /// they stop rewinding, so they are told only once each time.
+    pub reported_oldest_frame: bool,
 }
 
 impl Rewind {
@@ -70,3 +445,128 @@ impl Rewind {
         self.cooldown.is_sign_negative()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Frame in which every field differs from the default Frame. Written without a rest
+    /// pattern, so that a field added to Frame must be added here too.
+    fn full_frame() -> Frame {
+        let steering = Steering::new(Pos::new(3, 4), Pos::new(2, 2));
+        Frame {
+            player_steering: Some(steering.clone()),
+            player_translation: (4., 5.),
+            equipped: Some(ToolType::BreakBlocksBelow(2)),
+            keys: vec![Pos::new(1, 1)].into_iter().collect(),
+            held_keys: vec![KeyColour::Red].into_iter().collect(),
+            tools: vec![Pos::new(2, 2)].into_iter().collect(),
+            removed_tiles: vec![Pos::new(3, 3)].into_iter().collect(),
+            placed_tiles: vec![(Pos::new(4, 4), "Ladder".to_string())]
+                .into_iter()
+                .collect(),
+            fired_triggers: vec![Pos::new(5, 5)].into_iter().collect(),
+            pressed_triggers: vec![Pos::new(6, 6)].into_iter().collect(),
+            pushables: vec![(Pos::new(7, 7), Pos::new(8, 8))].into_iter().collect(),
+            mobs: vec![MobFrame {
+                spawn: Pos::new(9, 9),
+                steering,
+                ..MobFrame::default()
+            }],
+            dynamite: vec![Dynamite {
+                pos: Pos::new(10, 10),
+                ..Dynamite::default()
+            }],
+        }
+    }
+
+    /// A Frame that differs from the Frames made with other indices.
+    fn frame(index: i32) -> Frame {
+        Frame {
+            player_translation: (index as f32, 0.),
+            keys: (0..index % 5).map(|x| Pos::new(x, 0)).collect(),
+            ..Frame::default()
+        }
+    }
+
+    #[test]
+    fn every_field_round_trips_through_a_delta() {
+        let mut frame = Frame::default();
+        FrameDelta::between(&Frame::default(), &full_frame()).apply_to(&mut frame);
+        assert_eq!(frame, full_frame());
+
+        let mut frame = full_frame();
+        FrameDelta::between(&full_frame(), &Frame::default()).apply_to(&mut frame);
+        assert_eq!(frame, Frame::default());
+    }
+
+    #[test]
+    fn unchanged_fields_are_left_out_of_a_delta() {
+        let mut frame = full_frame();
+        FrameDelta::between(&Frame::default(), &Frame::default()).apply_to(&mut frame);
+        assert_eq!(frame, full_frame());
+    }
+
+    #[test]
+    fn frames_older_than_the_limit_are_dropped() {
+        let mut history = History::new(1.);
+        for index in 0..100 {
+            history.tick(0.1);
+            history.push_frame(frame(index));
+        }
+        assert!(history.is_truncated());
+        // One second at ten Frames per second, plus the Frame recorded exactly at the limit.
+        assert!(
+            history.frames.len() <= 11,
+            "{} frames",
+            history.frames.len()
+        );
+        assert!(history.frames.front().expect("No frames.").is_keyframe());
+        let oldest = history.frames.front().expect("No frames.").recorded_at;
+        assert!(oldest >= history.clock - 1. - f32::EPSILON);
+    }
+
+    #[test]
+    fn keyframes_are_spaced_evenly() {
+        let mut history = History::new(1000.);
+        for index in 0..100 {
+            history.tick(0.1);
+            history.push_frame(frame(index));
+        }
+        let keyframes = history
+            .frames
+            .iter()
+            .enumerate()
+            .filter(|(_, stored)| stored.is_keyframe())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert_eq!(keyframes, vec![0, 32, 64, 96]);
+    }
+
+    #[test]
+    fn rewinding_pieces_every_frame_back_together() {
+        let mut history = History::new(1000.);
+        for index in 0..100 {
+            history.tick(0.1);
+            history.push_frame(frame(index));
+        }
+        for index in (0..100).rev() {
+            assert_eq!(history.pop_frame(), Some(frame(index)));
+        }
+        assert_eq!(history.pop_frame(), None);
+    }
+
+    #[test]
+    fn rewinding_after_dropping_frames_pieces_every_frame_back_together() {
+        let mut history = History::new(5.);
+        for index in 0..100 {
+            history.tick(0.1);
+            history.push_frame(frame(index));
+        }
+        let kept = history.frames.len() as i32;
+        for index in (100 - kept..100).rev() {
+            assert_eq!(history.pop_frame(), Some(frame(index)));
+        }
+        assert_eq!(history.pop_frame(), None);
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// The player input for a single fixed tick. This is the only input that the core game play
+/// systems read, which means the game play can be driven by something other than the keyboard.
+///
+/// During normal play, the InputSnapshotSystem fills this resource from the InputHandler.
+/// A headless Simulation sets it directly.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct InputSnapshot {
+    /// The value of the horizontal movement axis, in the range [-1.0, 1.0].
+    pub move_x: f32,
+    /// The value of the vertical movement axis, in the range [-1.0, 1.0].
+    pub move_y: f32,
+    /// Whether the jump key is down. This key is also used to use tools.
+    pub jump: bool,
+}
+
+impl InputSnapshot {
+    pub fn new(move_x: f32, move_y: f32, jump: bool) -> Self {
+        InputSnapshot {
+            move_x,
+            move_y,
+            jump,
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use crate::levels::KeyColour;
+use std::collections::BTreeSet;
+
+/// The coloured keys that the player holds. These are kept apart from the keys in the
+/// WinCondition: coloured keys open locks, not the exit door.
+#[derive(Debug, Default)]
+pub struct KeyRing {
+    pub held: BTreeSet<KeyColour>,
+}
@@ -5,8 +5,13 @@ mod debug_lines;
 mod event_readers;
 mod gui;
 mod history;
+mod input;
+mod key_ring;
 mod music;
+mod replay;
+mod save;
 mod tilemap;
+mod triggers;
 mod userdata;
 mod win;
 
@@ -17,7 +22,12 @@ pub use self::debug_lines::*;
 pub use self::event_readers::*;
 pub use self::gui::*;
 pub use self::history::*;
+pub use self::input::*;
+pub use self::key_ring::*;
 pub use self::music::*;
+pub use self::replay::*;
+pub use self::save::*;
 pub use self::tilemap::*;
+pub use self::triggers::*;
 pub use self::userdata::*;
 pub use self::win::*;
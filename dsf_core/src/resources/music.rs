@@ -1,21 +1,76 @@
-use amethyst::audio::SourceHandle;
+use amethyst::audio::{AudioSink, SourceHandle};
+use amethyst::prelude::World;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 
-use std::iter::Cycle;
-use std::vec::IntoIter;
-
-/// This is a Music resource that is passed to Amethyst's DJSystem. It simply loops through a
-/// shuffled list of music tracks.
+/// This is a Music resource that is passed to Amethyst's DJSystem. It loops through a shuffled
+/// list of music tracks, unless the current level asks for a track of its own.
 /// In the (far) future, it should intelligently play music based on the level's atmosphere etc.
 pub struct Music {
-    pub music: Cycle<IntoIter<SourceHandle>>,
+    /// The file name and handle of each track, in shuffled order.
+    tracks: Vec<(String, SourceHandle)>,
+    /// Index of the track in the shuffled list that plays next.
+    next: usize,
+    /// Index of the track that plays on repeat instead of the shuffled list, if any.
+    preferred: Option<usize>,
 }
 
 impl Music {
-    pub fn new(mut tracks: Vec<SourceHandle>) -> Self {
+    pub fn new(mut tracks: Vec<(String, SourceHandle)>) -> Self {
         tracks.shuffle(&mut thread_rng());
-        let music = tracks.into_iter().cycle();
-        Music { music }
+        Music {
+            tracks,
+            next: 0,
+            preferred: None,
+        }
+    }
+
+    /// The track that the DJSystem should play once the current track is over.
+    pub fn next_track(&mut self) -> Option<SourceHandle> {
+        if let Some(index) = self.preferred {
+            return self.tracks.get(index).map(|(_, handle)| handle.clone());
+        }
+        let handle = self
+            .tracks
+            .get(self.next)
+            .map(|(_, handle)| handle.clone())?;
+        self.next = (self.next + 1) % self.tracks.len();
+        Some(handle)
+    }
+
+    /// Plays the track with the given file name on repeat. If the given track is None, goes back
+    /// to the shuffled list. Returns true if this changed the preferred track.
+    fn prefer(&mut self, track: Option<&str>) -> bool {
+        let preferred = track.and_then(|track| {
+            let index = self.tracks.iter().position(|(name, _)| name == track);
+            if index.is_none() && !self.tracks.is_empty() {
+                error!(
+                    "Music track {:?} does not exist, playing the usual music instead.",
+                    track
+                );
+            }
+            index
+        });
+        let changed = preferred != self.preferred;
+        self.preferred = preferred;
+        changed
+    }
+}
+
+/// Switches the music over to the given track, or back to the shuffled list if the given track
+/// is None. A newly preferred track starts right away; the shuffled list only resumes once the
+/// current track is over.
+///
+/// Does nothing if the world has no Music, which is the case in a headless simulation.
+pub fn prefer_music(world: &mut World, track: Option<&str>) {
+    let start_now = match world.try_fetch_mut::<Music>() {
+        Some(mut music) => music.prefer(track) && music.preferred.is_some(),
+        None => false,
+    };
+    if start_now {
+        if let Some(sink) = world.try_fetch::<AudioSink>() {
+            // The DJSystem notices the empty sink and asks for the next track.
+            sink.stop();
+        }
     }
 }
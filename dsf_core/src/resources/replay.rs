@@ -0,0 +1,183 @@
+use crate::levels::hash_level;
+use crate::resources::InputSnapshot;
+use crate::utility::files::{get_assets_dir, get_replays_dir};
+use amethyst::config::{Config, ConfigError};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A recording of all game play input of a single attempt at a level, one InputSnapshot per fixed
+/// tick. Feeding these inputs back into the game reproduces the attempt exactly.
+///
+/// Replays are stored as .replay.ron files. Identical consecutive inputs are stored only once,
+/// together with the number of ticks they were held, to keep the files small and readable.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct Replay {
+    /// The level that was played. If possible, this is stored relative to the assets directory.
+    pub level_file: PathBuf,
+    /// Hash of the level at the time of recording, see hash_level. If the level was changed
+    /// since, the replay will most likely no longer reproduce the same game.
+    pub level_hash: u64,
+    /// Every input, paired with the number of consecutive ticks it was held.
+    pub inputs: Vec<(u32, InputSnapshot)>,
+    /// Whether the game was rewound, an action was undone, or the player respawned at a
+    /// checkpoint during the attempt. None of those are input, so feeding the inputs back into
+    /// the game would not reproduce such an attempt.
+    pub interrupted: bool,
+}
+
+impl Replay {
+    /// Creates an empty replay for the given level file.
+    pub fn new(level_file: &PathBuf) -> Self {
+        let level_hash = hash_level(level_file).unwrap_or_else(|err| {
+            error!("Failed to hash level {:?}: {:?}", level_file, err);
+            0
+        });
+        Replay {
+            level_file: level_file
+                .strip_prefix(get_assets_dir())
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| level_file.clone()),
+            level_hash,
+            inputs: vec![],
+            interrupted: false,
+        }
+    }
+
+    /// Loads a replay from the given file and checks it against the current version of the level.
+    /// A mismatch is logged, but is not an error; the replay may simply diverge from the original.
+    pub fn load_replay(replay_file: &PathBuf) -> Result<Self, ConfigError> {
+        let replay = Replay::load(replay_file)?;
+        if !replay.matches_level() {
+            warn!(
+                "Level {:?} was changed since replay {:?} was recorded. Playback may diverge.",
+                replay.level_file, replay_file
+            );
+        }
+        Ok(replay)
+    }
+
+    /// The absolute path to the level file.
+    pub fn level_path(&self) -> PathBuf {
+        if self.level_file.is_absolute() {
+            self.level_file.clone()
+        } else {
+            get_assets_dir().join(&self.level_file)
+        }
+    }
+
+    /// Whether the level is still the same as when this replay was recorded.
+    pub fn matches_level(&self) -> bool {
+        hash_level(&self.level_path())
+            .map(|hash| hash == self.level_hash)
+            .unwrap_or(false)
+    }
+
+    /// Whether feeding the inputs back into the game reproduces the attempt. Only replayable
+    /// replays are saved, and kept as best runs.
+    pub fn is_replayable(&self) -> bool {
+        !self.interrupted
+    }
+
+    /// Append the input for a single tick.
+    pub fn push(&mut self, input: InputSnapshot) {
+        match self.inputs.last_mut() {
+            Some((ticks, last_input)) if *last_input == input => *ticks += 1,
+            _ => self.inputs.push((1, input)),
+        }
+    }
+
+    /// The total number of ticks recorded.
+    pub fn nr_ticks(&self) -> usize {
+        self.inputs.iter().map(|(ticks, _)| *ticks as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Iterates over the recorded inputs, one per tick.
+    pub fn iter(&self) -> impl Iterator<Item = InputSnapshot> + '_ {
+        self.inputs
+            .iter()
+            .flat_map(|(ticks, input)| (0..*ticks).map(move |_| *input))
+    }
+}
+
+/// The file that the latest replay of the given level is written to.
+pub fn replay_file_for_level(level_file: &PathBuf) -> PathBuf {
+    let level_name = level_file
+        .file_stem()
+        .expect("Level file has no name.")
+        .to_str()
+        .expect("Level file name did not contain valid unicode.");
+    get_replays_dir().join(format!("{}.replay.ron", level_name))
+}
+
+/// Resource that records the game play input while the player is playing a level.
+#[derive(Debug, Default)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+    /// Recording stops once the level is completed, there is nothing left to record after that.
+    pub finished: bool,
+}
+
+impl ReplayRecorder {
+    pub fn new(level_file: &PathBuf) -> Self {
+        ReplayRecorder {
+            replay: Replay::new(level_file),
+            finished: false,
+        }
+    }
+
+    pub fn record(&mut self, input: InputSnapshot) {
+        if !self.finished {
+            self.replay.push(input);
+        }
+    }
+
+    /// Marks the replay as no longer replayable, because the game state was changed by something
+    /// other than input: a rewind, an undo or a respawn at a checkpoint.
+    pub fn interrupt(&mut self) {
+        if !self.finished && !self.replay.interrupted {
+            info!("The game state changed without input, this attempt cannot be replayed.");
+            self.replay.interrupted = true;
+        }
+    }
+}
+
+/// Resource that feeds the inputs from a replay back into the game, instead of the keyboard.
+#[derive(Debug, Default)]
+pub struct ReplayPlayback {
+    inputs: Vec<InputSnapshot>,
+    next_tick: usize,
+    active: bool,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: &Replay) -> Self {
+        ReplayPlayback {
+            inputs: replay.iter().collect(),
+            next_tick: 0,
+            active: true,
+        }
+    }
+
+    /// If true, input should come from this playback rather than from the keyboard.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Returns the input for the next tick. Once the replay runs out, this returns neutral input.
+    pub fn next_input(&mut self) -> InputSnapshot {
+        let input = self.inputs.get(self.next_tick).copied().unwrap_or_default();
+        self.next_tick += 1;
+        input
+    }
+
+    /// True once all recorded inputs have been played back.
+    pub fn is_finished(&self) -> bool {
+        self.next_tick >= self.inputs.len()
+    }
+}
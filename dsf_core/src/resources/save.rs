@@ -0,0 +1,95 @@
+use crate::levels::hash_level;
+use crate::resources::{Frame, History, Replay};
+use crate::utility::files::get_saves_dir;
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// The progress of a level that the player left before finishing it. Resuming the save puts the
+/// level back in exactly the state the player left it in.
+///
+/// Saves are stored as .save.ron files, one per level. A save is only valid for the version of
+/// the level it was made on; if the level changed since, the save is discarded.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct SaveGame {
+    /// Hash of the level at the time of saving. See hash_level.
+    pub level_hash: u64,
+    /// How many seconds the player spent in the level before saving.
+    pub elapsed_seconds: f32,
+    /// The state of the level: the player's steering and equipped tool, the keys and tools that
+    /// are left, broken and placed tiles, and so on.
+    pub frame: Frame,
+    /// The rewind history, so the player can still rewind after resuming.
+    pub history: Option<History>,
+    /// The input recorded so far. Recording continues from here after resuming, so that the replay
+    /// still covers the whole attempt.
+    pub replay: Replay,
+}
+
+impl SaveGame {
+    /// Writes this save for the given level, replacing any earlier save of that level.
+    pub fn save(&self, level_file: &PathBuf) {
+        let save_file = save_file_for_level(level_file);
+        match self.write(&save_file) {
+            Ok(()) => info!("Saved progress to {:?}", save_file),
+            Err(err) => error!("Failed to save progress to {:?}: {:?}", save_file, err),
+        }
+    }
+
+    /// Loads the save of the given level, if there is one that still matches the level file.
+    ///
+    /// Saves that cannot be read, or that were made on an earlier version of the level, are
+    /// deleted.
+    pub fn load_for_level(level_file: &PathBuf) -> Option<SaveGame> {
+        let save_file = save_file_for_level(level_file);
+        if !save_file.is_file() {
+            return None;
+        }
+        let save = match SaveGame::load(&save_file) {
+            Ok(save) => save,
+            Err(err) => {
+                error!(
+                    "Failed to load save {:?}, discarding it: {:?}",
+                    save_file, err
+                );
+                SaveGame::delete(level_file);
+                return None;
+            }
+        };
+        let matches_level = hash_level(level_file)
+            .map(|hash| hash == save.level_hash)
+            .unwrap_or(false);
+        if !matches_level {
+            info!(
+                "Level {:?} was changed since it was saved, discarding save {:?}.",
+                level_file, save_file
+            );
+            SaveGame::delete(level_file);
+            return None;
+        }
+        Some(save)
+    }
+
+    /// Deletes the save of the given level, if there is one.
+    pub fn delete(level_file: &PathBuf) {
+        let save_file = save_file_for_level(level_file);
+        if save_file.is_file() {
+            fs::remove_file(&save_file).unwrap_or_else(|err| {
+                error!("Failed to delete save {:?}: {:?}", save_file, err);
+            });
+        }
+    }
+}
+
+/// The file that the save of the given level is written to.
+fn save_file_for_level(level_file: &PathBuf) -> PathBuf {
+    let level_name = level_file
+        .file_stem()
+        .expect("Level file has no name.")
+        .to_str()
+        .expect("Level file name did not contain valid unicode.");
+    get_saves_dir().join(format!("{}.save.ron", level_name))
+}
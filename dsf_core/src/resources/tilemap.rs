@@ -1,12 +1,34 @@
 use crate::components::*;
 use crate::levels::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 #[derive(Debug, Default)]
 pub struct TileMap {
     pub pos: Pos,
     pub dimens: Pos,
+    pub wrapping: Wrapping,
+    pub hazards: HazardSettings,
+    pub metadata: LevelMetadata,
+    /// Returned for every position beyond a border that acts as a wall.
+    boundary: TileDefinition,
     tiles: HashMap<Pos, Tile>,
+    /// Tiles that were removed during play, for instance by mining them. They are kept around so
+    /// they can be put back when the game is rewound.
+    removed: HashMap<Pos, Tile>,
+    /// Tiles that were added during play, for instance by placing a ladder. Maps each anchor to
+    /// its tile definition key.
+    placed: BTreeMap<Pos, String>,
+    /// Tiles that are hidden because of a trigger. Maps each anchor to its tile definition key.
+    hidden: BTreeMap<Pos, String>,
+    /// Tiles that a pushable block has fallen onto. Maps each anchor to its tile definition key.
+    /// They are put back once the block has moved off them again.
+    covered: BTreeMap<Pos, String>,
+    /// Anchors of the tiles whose side collision is flipped because of a trigger.
+    flipped: BTreeSet<Pos>,
+    /// A copy of every tile definition, with side collision flipped. Used for flipped tiles.
+    flipped_defs: HashMap<String, TileDefinition>,
+    /// Properties of individual tiles, keyed by the position of the tile in the level file.
+    properties: BTreeMap<Pos, TileProperties>,
     tile_defs: TileDefinitions,
 }
 
@@ -35,29 +57,243 @@ impl TileMap {
         TileMap {
             pos: level.pos,
             dimens: level.dimens,
+            wrapping: level.wrapping,
+            hazards: level.hazards,
+            metadata: level.metadata,
+            boundary: TileDefinition::boundary(),
             tiles,
+            removed: HashMap::new(),
+            placed: BTreeMap::new(),
+            hidden: BTreeMap::new(),
+            covered: BTreeMap::new(),
+            flipped: BTreeSet::new(),
+            flipped_defs: flip_collides_side(&tile_defs),
+            properties: level.properties,
             tile_defs,
         }
     }
     pub fn get_tile(&self, pos: &Pos) -> Option<&TileDefinition> {
-        self.tiles
-            .get(pos)
-            .map(|tile| match tile {
-                Tile::Dummy(pos) => match self.tiles.get(pos) {
-                    Some(Tile::TileDefKey(key)) => Some(key),
-                    _ => {
-                        error!("Error! Dummy position lookup failed for tile {:?}", tile);
-                        None
-                    }
-                },
-                Tile::TileDefKey(key) => Some(key),
-            })
-            .flatten()
-            .map(|tile_def_key| self.tile_defs.get(tile_def_key))
+        if self.is_boundary(pos) {
+            return Some(&self.boundary);
+        }
+        let anchor = self.get_anchor(pos)?;
+        let tile_def_key = match self.tiles.get(&anchor) {
+            Some(Tile::TileDefKey(key)) => key,
+            tile => {
+                error!("Error! Dummy position lookup failed for tile {:?}", tile);
+                return None;
+            }
+        };
+        if self.flipped.contains(&anchor) {
+            if let Some(tile_def) = self.flipped_defs.get(tile_def_key) {
+                return Some(tile_def);
+            }
+        }
+        Some(self.tile_defs.get(tile_def_key))
+    }
+    pub fn get_tile_def_key(&self, pos: &Pos) -> Option<&String> {
+        match self.tiles.get(pos)? {
+            Tile::Dummy(anchor) => match self.tiles.get(anchor) {
+                Some(Tile::TileDefKey(key)) => Some(key),
+                _ => None,
+            },
+            Tile::TileDefKey(key) => Some(key),
+        }
+    }
+    /// Returns the position of the anchor (the bottom-left position) of the tile at the given
+    /// position.
+    pub fn get_anchor(&self, pos: &Pos) -> Option<Pos> {
+        match self.tiles.get(pos)? {
+            Tile::Dummy(anchor) => Some(*anchor),
+            Tile::TileDefKey(_) => Some(*pos),
+        }
+    }
+    /// True iff the given position lies beyond a border of the level that acts as a wall.
+    pub fn is_boundary(&self, pos: &Pos) -> bool {
+        let outside_x = pos.x < self.pos.x || pos.x >= self.pos.x + self.dimens.x;
+        let outside_y = pos.y < self.pos.y || pos.y >= self.pos.y + self.dimens.y;
+        (outside_x && self.wrapping.x == EdgeRule::Wall)
+            || (outside_y && self.wrapping.y == EdgeRule::Wall)
+    }
+    /// The properties of the tile that the level file puts at the given position, if it has any.
+    /// Tiles that move around during play keep the properties of their original position.
+    pub fn get_properties(&self, pos: &Pos) -> Option<&TileProperties> {
+        self.properties.get(pos)
+    }
+    /// The id of the exit door anchored at the given position. Doors without an "id" property are
+    /// the main exit.
+    pub fn exit_id(&self, pos: &Pos) -> String {
+        self.get_properties(pos)
+            .and_then(|properties| properties.id("id"))
+            .unwrap_or(MAIN_EXIT)
+            .to_string()
+    }
+    pub fn tile_defs(&self) -> &TileDefinitions {
+        &self.tile_defs
     }
     pub fn remove_tile(&mut self, pos: &Pos) {
-        self.tiles.remove(pos);
+        if let Some(tile) = self.tiles.remove(pos) {
+            self.removed.insert(*pos, tile);
+        }
+    }
+    /// Removes the tile anchored at the given position, including all its dummies. Like
+    /// remove_tile, the removed positions are kept around so they can be put back when rewinding.
+    pub fn remove_whole_tile(&mut self, anchor: &Pos) {
+        let dimens = match self.tiles.get(anchor) {
+            Some(Tile::TileDefKey(key)) => self.tile_defs.get(key).dimens,
+            _ => return,
+        };
+        for x in 0..dimens.x {
+            for y in 0..dimens.y {
+                self.remove_tile(&anchor.append_xy(x, y));
+            }
+        }
     }
+    /// Puts back a tile that was removed earlier.
+    /// If the restored tile is the anchor of a tile definition, this returns its key.
+    pub fn restore_tile(&mut self, pos: &Pos) -> Option<String> {
+        let tile = self.removed.remove(pos)?;
+        let key = match &tile {
+            Tile::TileDefKey(key) => Some(key.clone()),
+            Tile::Dummy(_) => None,
+        };
+        self.tiles.insert(*pos, tile);
+        key
+    }
+    /// Moves the tile anchored at the first position so that it is anchored at the second position,
+    /// for each pair of positions. All tiles are lifted before any of them are put down, so tiles
+    /// can move into each other's old positions.
+    ///
+    /// Tiles that are in the way are covered rather than replaced: they are lifted out of the map
+    /// and put back as soon as all their positions are free again. That way, a pushable block can
+    /// come to rest on top of a key, a trigger or another tile that does not provide a platform.
+    pub fn move_tiles(&mut self, moves: &[(Pos, Pos)]) {
+        let lifted = moves
+            .iter()
+            .filter_map(|(from, to)| self.lift_tile(from).map(|key| (*to, key)))
+            .collect::<Vec<_>>();
+        let uncovered = self
+            .covered
+            .iter()
+            .filter(|(anchor, key)| {
+                let dimens = self.tile_defs.get(key).dimens;
+                (0..dimens.x)
+                    .flat_map(|x| (0..dimens.y).map(move |y| anchor.append_xy(x, y)))
+                    .all(|pos| !self.tiles.contains_key(&pos))
+            })
+            .map(|(anchor, _)| *anchor)
+            .collect::<Vec<Pos>>();
+        for anchor in uncovered {
+            if let Some(key) = self.covered.remove(&anchor) {
+                self.put_tile(&anchor, key);
+            }
+        }
+        for (to, key) in &lifted {
+            let dimens = self.tile_defs.get(key).dimens;
+            let mut in_the_way = (0..dimens.x)
+                .flat_map(|x| (0..dimens.y).map(move |y| to.append_xy(x, y)))
+                .filter_map(|pos| self.get_anchor(&pos))
+                .collect::<Vec<Pos>>();
+            in_the_way.sort();
+            in_the_way.dedup();
+            for anchor in in_the_way {
+                if let Some(covered_key) = self.lift_tile(&anchor) {
+                    self.covered.insert(anchor, covered_key);
+                }
+            }
+        }
+        lifted
+            .into_iter()
+            .for_each(|(to, key)| self.put_tile(&to, key));
+    }
+    /// Adds a tile that was not part of the level, anchored at the given position.
+    pub fn place_tile(&mut self, anchor: &Pos, key: &str) {
+        self.put_tile(anchor, key.to_string());
+        self.placed.insert(*anchor, key.to_string());
+    }
+    /// Removes a tile that was added with place_tile.
+    pub fn unplace_tile(&mut self, anchor: &Pos) {
+        if self.placed.remove(anchor).is_some() {
+            self.lift_tile(anchor);
+        }
+    }
+    /// The anchors and keys of all tiles that were added during play.
+    pub fn placed_tiles(&self) -> &BTreeMap<Pos, String> {
+        &self.placed
+    }
+    /// Hides the tile anchored at the given position until show_tile is called.
+    pub fn hide_tile(&mut self, anchor: &Pos) {
+        if let Some(key) = self.lift_tile(anchor) {
+            self.hidden.insert(*anchor, key);
+        }
+    }
+    /// Brings back a tile that was hidden with hide_tile. Returns its key.
+    pub fn show_tile(&mut self, anchor: &Pos) -> Option<String> {
+        let key = self.hidden.remove(anchor)?;
+        self.put_tile(anchor, key.clone());
+        Some(key)
+    }
+    /// The anchors of all tiles that are hidden.
+    pub fn hidden_tiles(&self) -> impl Iterator<Item = &Pos> {
+        self.hidden.keys()
+    }
+    /// Sets which tiles have their side collision flipped. Tiles not in the set collide normally.
+    pub fn set_flipped(&mut self, flipped: BTreeSet<Pos>) {
+        self.flipped = flipped;
+    }
+    /// Removes the tile anchored at the given position, including all its dummies.
+    fn lift_tile(&mut self, anchor: &Pos) -> Option<String> {
+        let key = match self.tiles.get(anchor) {
+            Some(Tile::TileDefKey(key)) => key.clone(),
+            _ => return None,
+        };
+        let dimens = self.tile_defs.get(&key).dimens;
+        for x in 0..dimens.x {
+            for y in 0..dimens.y {
+                self.tiles.remove(&anchor.append_xy(x, y));
+            }
+        }
+        Some(key)
+    }
+    fn put_tile(&mut self, anchor: &Pos, key: String) {
+        let dimens = self.tile_defs.get(&key).dimens;
+        for x in 0..dimens.x {
+            for y in 0..dimens.y {
+                let tile = if x == 0 && y == 0 {
+                    Tile::TileDefKey(key.clone())
+                } else {
+                    Tile::Dummy(*anchor)
+                };
+                if let Some(replaced_value) = self.tiles.insert(anchor.append_xy(x, y), tile) {
+                    error!(
+                        "Error! Moving tile {:?} to {:?} replaced {:?}",
+                        key, anchor, replaced_value
+                    );
+                }
+            }
+        }
+    }
+    /// The positions of all tiles that were removed during play.
+    pub fn removed_tiles(&self) -> impl Iterator<Item = &Pos> {
+        self.removed.keys()
+    }
+}
+
+fn flip_collides_side(tile_defs: &TileDefinitions) -> HashMap<String, TileDefinition> {
+    tile_defs
+        .map
+        .iter()
+        .map(|(key, tile_def)| {
+            let mut flipped = tile_def.clone();
+            let collision = flipped.collision.get_or_insert(CollisionDefinition {
+                collides_top: false,
+                collides_side: false,
+                collides_bottom: false,
+            });
+            collision.collides_side = !collision.collides_side;
+            (key.clone(), flipped)
+        })
+        .collect()
 }
 
 #[derive(Debug)]
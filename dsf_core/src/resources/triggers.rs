@@ -0,0 +1,67 @@
+use crate::components::Pos;
+use crate::levels::{TriggerEffect, TriggerLink};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Keeps track of the triggers in the level: what they are linked to and which of them are fired.
+#[derive(Debug, Default)]
+pub struct Triggers {
+    /// Maps the position of each trigger to the tiles it toggles.
+    links: BTreeMap<Pos, Vec<TriggerLink>>,
+    /// The positions of the triggers that are currently fired.
+    pub fired: BTreeSet<Pos>,
+    /// The positions of the triggers that the player overlapped during the previous tick. Used to
+    /// detect the moment the player steps onto a trigger.
+    pub pressed: BTreeSet<Pos>,
+}
+
+impl Triggers {
+    pub fn new(links: BTreeMap<Pos, Vec<TriggerLink>>) -> Self {
+        Triggers {
+            links,
+            fired: BTreeSet::new(),
+            pressed: BTreeSet::new(),
+        }
+    }
+
+    /// The anchors of the tiles that should currently be hidden.
+    ///
+    /// A tile that is the target of an Appear link starts out hidden. Every fired Appear or
+    /// Disappear link to the tile toggles it once more.
+    pub fn hidden_targets(&self) -> BTreeSet<Pos> {
+        let starts_hidden = self
+            .all_links()
+            .filter(|link| link.effect == TriggerEffect::Appear)
+            .map(|link| link.target)
+            .collect::<BTreeSet<Pos>>();
+        let toggled = self.toggled(|effect| effect != TriggerEffect::FlipCollidesSide);
+        starts_hidden
+            .symmetric_difference(&toggled)
+            .copied()
+            .collect()
+    }
+
+    /// The anchors of the tiles that should currently have their side collision flipped.
+    pub fn flipped_targets(&self) -> BTreeSet<Pos> {
+        self.toggled(|effect| effect == TriggerEffect::FlipCollidesSide)
+    }
+
+    /// The targets that are toggled an odd number of times by fired links with a matching effect.
+    fn toggled(&self, matches: impl Fn(TriggerEffect) -> bool) -> BTreeSet<Pos> {
+        let mut toggled = BTreeSet::new();
+        self.fired
+            .iter()
+            .filter_map(|trigger| self.links.get(trigger))
+            .flatten()
+            .filter(|link| matches(link.effect))
+            .for_each(|link| {
+                if !toggled.remove(&link.target) {
+                    toggled.insert(link.target);
+                }
+            });
+        toggled
+    }
+
+    fn all_links(&self) -> impl Iterator<Item = &TriggerLink> {
+        self.links.values().flatten()
+    }
+}
@@ -2,7 +2,7 @@ use crate::components::Pos;
 use crate::utility::files::get_user_cache_file;
 use amethyst::config::Config;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 /// These are some transient values to improve user experience.
 #[derive(Debug, Deserialize, Serialize, Default)]
@@ -11,6 +11,10 @@ pub struct UserCache {
     /// This maps the adventure file name (e.g. "default.ron") to the last position the
     /// player's cursor was at.
     pub adventure_map_pos: HashMap<String, Pos>,
+    /// This keeps track of the exits the player has left each level through.
+    /// This maps the level file name (e.g. "level_001.ron") to the ids of those exits.
+    #[serde(default)]
+    pub exits_taken: HashMap<String, BTreeSet<String>>,
 }
 
 impl UserCache {
@@ -21,6 +25,27 @@ impl UserCache {
         });
     }
 
+    pub fn save_exit_taken(&mut self, level_file_name: String, exit: String) {
+        if self
+            .exits_taken
+            .entry(level_file_name)
+            .or_default()
+            .insert(exit)
+        {
+            self.write(get_user_cache_file()).unwrap_or_else(|err| {
+                error!("Failed to save {:?} because error: {:?}", self, err);
+            });
+        }
+    }
+
+    /// Whether the player has ever left the given level through the exit with the given id.
+    pub fn has_taken_exit(&self, level_file_name: &str, exit: &str) -> bool {
+        self.exits_taken
+            .get(level_file_name)
+            .map(|exits| exits.contains(exit))
+            .unwrap_or(false)
+    }
+
     pub fn get_initial_cursor_pos(&self, adventure_file_name: &str) -> Pos {
         self.adventure_map_pos
             .get(adventure_file_name)
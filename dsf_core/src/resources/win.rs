@@ -1,6 +1,7 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 use crate::components::*;
+use crate::levels::ContactRule;
 
 /// Maintains some information related to winning the level.
 /// In any given level, the player must collect all keys. Once all keys are collected, the exit door
@@ -10,15 +11,41 @@ pub struct WinCondition {
     /// The set of positions of keys that are left in the level. If this collection is empty, then
     /// the player has collected all keys and is free to finish the level by reaching the exit door.
     pub keys: HashSet<Pos>,
+    /// The positions of all keys in the level, collected or not.
+    pub all_keys: BTreeSet<Pos>,
     /// This is set to true when the player has collected all keys and then subsequently reached
     /// the exit door. If this is true, the player has completed the level.
     pub reached_open_door: bool,
+    /// The id of the exit door the player left the level through. This is set together with
+    /// reached_open_door.
+    pub exit_taken: Option<String>,
+    /// This is set when the player dies. It holds what killed them. Once the death animation is
+    /// over, the cause decides what happens next.
+    pub killed_by: Option<DeathCause>,
+}
+
+/// What killed the player. Every way to fail a level goes through the same death, only what
+/// happens after the death animation differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    /// The player touched a hazard tile, such as spikes. The player respawns as the level's
+    /// hazard settings decide.
+    Hazard,
+    /// The player fell further than the level's maximum safe fall height. The player respawns as
+    /// the level's hazard settings decide.
+    Fall,
+    /// A mob touched the player. The mob's contact rule decides whether the level is reset or
+    /// lost.
+    Mob(ContactRule),
+    /// The player left the level across a border that resets the level.
+    LeftLevel,
 }
 
 impl WinCondition {
     /// Add a key. Only to be used when loading a level.
     pub fn add_key(&mut self, pos: &Pos) {
         self.keys.insert(*pos);
+        self.all_keys.insert(*pos);
     }
     /// How many keys are left uncollected in the level.
     pub fn nr_keys_left(&self) -> usize {
@@ -33,4 +60,13 @@ impl WinCondition {
     pub fn all_keys_collected(&self) -> bool {
         self.keys.is_empty()
     }
+    /// Whether the player is dead and waiting to respawn. If this returns true, the player cannot
+    /// move.
+    pub fn is_dying(&self) -> bool {
+        self.killed_by.is_some()
+    }
+    /// Whether the player lost the level. If this returns true, the player can no longer move.
+    pub fn lost(&self) -> bool {
+        self.killed_by == Some(DeathCause::Mob(ContactRule::LoseLevel))
+    }
 }
@@ -0,0 +1,200 @@
+use amethyst::core::num::FloatConst;
+use amethyst::{
+    core::transform::Transform,
+    ecs::{prelude::World, Join, ReadStorage, WriteStorage},
+    prelude::{Builder, Config, WorldExt},
+    renderer::{palette::Srgba, resources::Tint, Transparent},
+};
+
+use crate::components::*;
+use crate::levels::{load_transform, DepthLayer};
+use crate::resources::*;
+use crate::simulation::Simulation;
+use std::path::PathBuf;
+use std::thread::{self, JoinHandle};
+
+/// The ghost is drawn in the player's colours, but mostly see-through.
+const GHOST_ALPHA: f32 = 0.4;
+
+/// Replays the player's fastest completion of a level, so they can race against it.
+///
+/// The ghost runs in a headless Simulation of its own. Nothing the ghost does affects the real
+/// game, and nothing the player does affects the ghost.
+pub struct Ghost {
+    simulation: Simulation,
+    inputs: Vec<InputSnapshot>,
+    next_tick: usize,
+}
+
+impl Ghost {
+    /// Loads the best run of the given level. Returns None if the player has not completed the
+    /// level yet, or if the level was changed since their best run.
+    pub fn load(level_file: &PathBuf, config: MovementConfig) -> Option<Ghost> {
+        let replay_file = best_replay_file_for_level(level_file);
+        if !replay_file.is_file() {
+            return None;
+        }
+        let replay = Replay::load(&replay_file)
+            .map_err(|err| error!("Failed to load best run {:?}: {:?}", replay_file, err))
+            .ok()?;
+        if !replay.matches_level() {
+            info!(
+                "Level {:?} was changed since the best run was recorded, not showing a ghost.",
+                level_file
+            );
+            return None;
+        }
+        let simulation = Simulation::load(level_file, config)
+            .map_err(|err| error!("Failed to load level {:?} for ghost: {:?}", level_file, err))
+            .ok()?;
+        Some(Ghost {
+            simulation,
+            inputs: replay.iter().collect(),
+            next_tick: 0,
+        })
+    }
+
+    /// Advances the ghost by a single tick. Once the run is over, the ghost stands still.
+    pub fn step(&mut self) {
+        if let Some(input) = self.inputs.get(self.next_tick) {
+            self.simulation.step(*input);
+            self.next_tick += 1;
+        }
+    }
+
+    /// Advances the ghost by the given number of ticks at once.
+    pub fn skip(&mut self, ticks: usize) {
+        (0..ticks).for_each(|_| self.step());
+    }
+
+    /// The ghost's steering, or None if the level has no player.
+    pub fn steering(&self) -> Option<Steering> {
+        self.simulation.player_steering()
+    }
+
+    /// The ghost's actual (centered) position, or None if the level has no player.
+    pub fn translation(&self) -> Option<(f32, f32)> {
+        self.simulation.player_translation()
+    }
+}
+
+/// The file that the fastest completion of the given level is written to.
+pub fn best_replay_file_for_level(level_file: &PathBuf) -> PathBuf {
+    let replay_file = replay_file_for_level(level_file);
+    let replay_name = replay_file
+        .file_name()
+        .expect("Replay file has no name.")
+        .to_str()
+        .expect("Replay file name did not contain valid unicode.")
+        .replace(".replay.ron", ".best.replay.ron");
+    replay_file.with_file_name(replay_name)
+}
+
+/// Keeps the given replay as the best run of the given level if it is faster than the current
+/// best run, or if there is no (valid) best run yet.
+///
+/// A run in which the player rewound, undid an action or respawned at a checkpoint is never kept,
+/// it does not replay faithfully. Other runs are played back in a Simulation first, and only kept
+/// if they complete the level on their own. That takes a while for a long run, so during play use
+/// spawn_best_run_check instead.
+pub fn save_if_best_run(level_file: &PathBuf, replay: &Replay, config: MovementConfig) {
+    if !replay.is_replayable() {
+        return;
+    }
+    let best_file = best_replay_file_for_level(level_file);
+    let current_best = if best_file.is_file() {
+        Replay::load(&best_file)
+            .ok()
+            .filter(|best| best.level_hash == replay.level_hash)
+            .map(|best| best.nr_ticks())
+    } else {
+        None
+    };
+    if current_best.map_or(false, |best| best <= replay.nr_ticks()) {
+        return;
+    }
+    let completes_level = match Simulation::load(level_file, config) {
+        Ok(mut simulation) => {
+            simulation.run_replay(replay);
+            simulation.win_condition().reached_open_door
+        }
+        Err(err) => {
+            error!(
+                "Failed to load level {:?} to check run: {:?}",
+                level_file, err
+            );
+            false
+        }
+    };
+    if !completes_level {
+        info!("Run does not replay faithfully, not keeping it as the best run.");
+        return;
+    }
+    match replay.write(&best_file) {
+        Ok(()) => info!(
+            "New best run of {} ticks, saved to {:?}",
+            replay.nr_ticks(),
+            best_file
+        ),
+        Err(err) => error!("Failed to save best run to {:?}: {:?}", best_file, err),
+    }
+}
+
+/// Runs save_if_best_run on a thread of its own, so the game does not stall while the run is
+/// played back. Join the returned handle before the game exits, or the best run may be lost.
+pub fn spawn_best_run_check(
+    level_file: PathBuf,
+    replay: Replay,
+    config: MovementConfig,
+) -> JoinHandle<()> {
+    thread::spawn(move || save_if_best_run(&level_file, &replay, config))
+}
+
+/// Creates the entity that shows the ghost: a see-through copy of the player.
+pub fn build_ghost(world: &mut World, ghost: &Ghost) {
+    let steering = match ghost.steering() {
+        Some(steering) => steering,
+        None => return,
+    };
+    let asset_type = AssetType::Animated(AnimType::Miner);
+    let mut transform = load_transform(
+        &steering.pos,
+        &DepthLayer::Player,
+        &steering.dimens,
+        &asset_type,
+    );
+    // Just behind the player, so the player is never hidden by their own ghost.
+    transform.set_translation_z(DepthLayer::Player.z() - 1.);
+    let animation = world
+        .read_resource::<Assets>()
+        .get_animated(&AnimType::Miner);
+    world
+        .create_entity()
+        .with(GhostTag)
+        .with(Transparent)
+        .with(Tint(Srgba::new(1., 1., 1., GHOST_ALPHA)))
+        .with(transform)
+        .with(animation)
+        .build();
+}
+
+/// Moves the ghost entity to where the ghost is in its simulation.
+pub fn update_ghost(world: &mut World, ghost: &Ghost) {
+    let (steering, (x, y)) = match (ghost.steering(), ghost.translation()) {
+        (Some(steering), Some(translation)) => (steering, translation),
+        _ => return,
+    };
+    world.exec(
+        |(ghost_tags, mut transforms): (ReadStorage<GhostTag>, WriteStorage<Transform>)| {
+            for (_, transform) in (&ghost_tags, &mut transforms).join() {
+                transform.set_translation_x(x);
+                transform.set_translation_y(y);
+                transform.set_rotation_y_axis(if steering.facing.x == Direction1D::Negative {
+                    f32::PI()
+                } else {
+                    0.
+                });
+            }
+        },
+    );
+}
@@ -0,0 +1,165 @@
+use amethyst::{
+    config::ConfigError,
+    core::{shred::Fetch, timing::Time, transform::Transform, Parent},
+    ecs::{prelude::World, Dispatcher, DispatcherBuilder, Join},
+    prelude::{Builder, WorldExt},
+};
+
+use crate::components::*;
+use crate::levels::{build_level, load_tile_definitions, Level, TileDefinitions};
+use crate::resources::*;
+use crate::systems;
+use std::path::PathBuf;
+
+/// The duration of a single simulation tick, in seconds. This matches the fixed time step that
+/// the game itself uses.
+pub const FIXED_SECONDS: f32 = 1. / 60.;
+
+/// Runs the core game play systems on a level without a window, renderer or audio output.
+///
+/// Each call to `step` advances the game by exactly one fixed tick, using the given input instead
+/// of the keyboard. The game rules run in the same order as in the game, see add_game_rules, so the
+/// same level and the same sequence of inputs produce the same result here as in the game.
+///
+/// This is useful for automated checks and tools. The game itself uses it to run the ghost of
+/// the player's best run, and to check that a run replays faithfully before keeping it.
+pub struct Simulation {
+    world: World,
+    dispatcher: Dispatcher<'static, 'static>,
+    ticks: u32,
+}
+
+impl Simulation {
+    /// Creates a new simulation of the given level. The level is loaded immediately.
+    pub fn new(level: Level, tile_defs: TileDefinitions, config: MovementConfig) -> Self {
+        let mut world = World::new();
+        let mut dispatcher = systems::add_game_rules(DispatcherBuilder::new(), &[]).build();
+        dispatcher.setup(&mut world);
+        // The PickupSystem attaches the equipped tool to the player as a child entity.
+        world.register::<Parent>();
+        let mut time = Time::default();
+        time.set_fixed_seconds(FIXED_SECONDS);
+        world.insert(time);
+        world.insert(config);
+        world.insert(InputSnapshot::default());
+        build_level(level, tile_defs, &mut world);
+        world.maintain();
+        Simulation {
+            world,
+            dispatcher,
+            ticks: 0,
+        }
+    }
+
+    /// Loads the given level file and the tile definitions from the world directory and creates
+    /// a simulation for them.
+    pub fn load(level_file: &PathBuf, config: MovementConfig) -> Result<Self, ConfigError> {
+        let tile_defs = load_tile_definitions()?;
+        let level = Level::load_migrated(level_file)?;
+        Ok(Simulation::new(level, tile_defs, config))
+    }
+
+    /// Advances the simulation by a single fixed tick, using the given input.
+    pub fn step(&mut self, input: InputSnapshot) {
+        *self.world.write_resource::<InputSnapshot>() = input;
+        self.dispatcher.dispatch_seq(&self.world);
+        self.world.maintain();
+        self.ticks += 1;
+    }
+
+    /// Moves the player to the given position, standing still. If there is no ground beneath
+    /// that position, the player starts falling on the next tick.
+    pub fn place_player(&mut self, pos: Pos) {
+        let players = self.world.read_storage::<Player>();
+        let mut steerings = self.world.write_storage::<Steering>();
+        let mut transforms = self.world.write_storage::<Transform>();
+        for (_, steering, transform) in (&players, &mut steerings, &mut transforms).join() {
+            steering.pos = pos;
+            steering.destination = pos;
+            steering.mode = SteeringMode::Grounded;
+            steering.landed_after_fall = None;
+            let (centered_x, centered_y) = steering.to_centered_coords(pos);
+            transform.set_translation_x(centered_x);
+            transform.set_translation_y(centered_y);
+        }
+    }
+
+    /// Adds a player of the given size to the simulation, standing still on the given position.
+    /// Use this for levels that contain no player of their own.
+    pub fn spawn_player(&mut self, pos: Pos, dimens: Pos) {
+        let steering = Steering::new(pos, dimens);
+        let (centered_x, centered_y) = steering.to_centered_coords(pos);
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(centered_x, centered_y, 0.);
+        self.world
+            .create_entity()
+            .with(Velocity::default())
+            .with(SteeringIntent::default())
+            .with(steering)
+            .with(Player::default())
+            .with(transform)
+            .build();
+    }
+
+    /// Feeds every input of the given replay into the simulation, one tick each.
+    /// Use this to check that a recorded solution still completes the level.
+    pub fn run_replay(&mut self, replay: &Replay) {
+        replay.iter().for_each(|input| self.step(input));
+    }
+
+    /// The number of ticks that have been simulated so far.
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    /// The number of seconds of game time that have been simulated so far.
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.ticks as f32 * FIXED_SECONDS
+    }
+
+    /// Returns a copy of the player's Steering, or None if the level contains no player.
+    pub fn player_steering(&self) -> Option<Steering> {
+        let players = self.world.read_storage::<Player>();
+        let steerings = self.world.read_storage::<Steering>();
+        (&players, &steerings)
+            .join()
+            .map(|(_, steering)| steering.clone())
+            .next()
+    }
+
+    /// Returns a copy of the player component, which among other things holds the equipped tool.
+    pub fn player(&self) -> Option<Player> {
+        let players = self.world.read_storage::<Player>();
+        (&players).join().copied().next()
+    }
+
+    /// Returns the player's actual (centered) position in the world, or None if the level
+    /// contains no player.
+    pub fn player_translation(&self) -> Option<(f32, f32)> {
+        let players = self.world.read_storage::<Player>();
+        let transforms = self.world.read_storage::<Transform>();
+        (&players, &transforms)
+            .join()
+            .map(|(_, transform)| (transform.translation().x, transform.translation().y))
+            .next()
+    }
+
+    pub fn win_condition(&self) -> Fetch<'_, WinCondition> {
+        self.world.read_resource::<WinCondition>()
+    }
+
+    pub fn tile_map(&self) -> Fetch<'_, TileMap> {
+        self.world.read_resource::<TileMap>()
+    }
+
+    /// Direct access to the simulated world, for anything not covered by the methods above.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Direct mutable access to the simulated world, for anything not covered by the methods
+    /// above.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+}
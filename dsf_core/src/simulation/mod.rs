@@ -0,0 +1,5 @@
+mod ghost;
+mod headless;
+
+pub use self::ghost::*;
+pub use self::headless::*;
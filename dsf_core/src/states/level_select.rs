@@ -50,9 +50,11 @@ impl<'a, 'b> LevelSelectState {
     /// This function will check what node the user currently has selected and act accordingly.
     ///
     /// - If the user selected a road, nothing will happen.
-    /// - If the user selected a level, that level will be opened in the Play state.
+    /// - If the user selected a level, that level will be opened in the Play state. If the user
+    ///   left that level unfinished before, it picks up where they left off.
     /// - If the user selected an adventure, that adventure will be opened in a nested LevelSelect state.
     fn select_node(world: &mut World) -> SimpleTrans {
+        world.insert(LevelSelectMessage::default());
         world.exec(
             |(adventure, pos_on_map): (Read<Adventure>, Read<PositionOnMap>)| {
                 let selected_node = adventure.nodes.get(&pos_on_map.pos);
@@ -61,7 +63,7 @@ impl<'a, 'b> LevelSelectState {
                         details: NodeDetails::Level(level_name),
                         ..
                     })) => {
-                        let play_state = PlayState::new(get_levels_dir().join(level_name));
+                        let play_state = PlayState::resumable(get_levels_dir().join(level_name));
                         Trans::Push(Box::new(play_state))
                     }
                     _ => Trans::None,
@@ -70,6 +72,38 @@ impl<'a, 'b> LevelSelectState {
         )
     }
 
+    /// Call this when the user wants to watch the latest replay of the selected level.
+    /// If the user selected a level that has a replay, that replay will be played in the Play
+    /// state. Otherwise, nothing will happen.
+    fn watch_replay(world: &mut World) -> SimpleTrans {
+        world.insert(LevelSelectMessage::default());
+        world.exec(
+            |(adventure, pos_on_map): (Read<Adventure>, Read<PositionOnMap>)| {
+                let selected_node = adventure.nodes.get(&pos_on_map.pos);
+                match selected_node {
+                    Some(MapElement::Node(AdventureNode {
+                        details: NodeDetails::Level(level_name),
+                        ..
+                    })) => {
+                        let replay_file = replay_file_for_level(&get_levels_dir().join(level_name));
+                        if !replay_file.is_file() {
+                            info!("There is no replay for level {:?}.", level_name);
+                            return Trans::None;
+                        }
+                        match Replay::load_replay(&replay_file) {
+                            Ok(replay) => Trans::Push(Box::new(PlayState::replay(replay))),
+                            Err(err) => {
+                                error!("Failed to load replay {:?}: {:?}", replay_file, err);
+                                Trans::None
+                            }
+                        }
+                    }
+                    _ => Trans::None,
+                }
+            },
+        )
+    }
+
     /// Prepare to start or resume.
     fn perform_setup(&self, world: &mut World) {
         UiHandles::add_ui(&UiType::Fps, world);
@@ -139,6 +173,10 @@ impl SimpleState for LevelSelectState {
                     key_code: VirtualKeyCode::Return,
                     scancode: _,
                 } => Self::select_node(data.world),
+                InputEvent::KeyReleased {
+                    key_code: VirtualKeyCode::R,
+                    scancode: _,
+                } => Self::watch_replay(data.world),
                 _ => Trans::None,
             },
         }
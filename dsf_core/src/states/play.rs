@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use std::thread::JoinHandle;
 
 use amethyst::prelude::WorldExt;
 
@@ -8,7 +9,7 @@ use amethyst::{
         get_animation_set, AnimationCommand, AnimationControlSet, AnimationSet, EndControl,
     },
     core::timing::Time,
-    ecs::{prelude::World, Entities, Join, ReadStorage, WriteStorage},
+    ecs::{prelude::World, Entities, Join, ReadStorage, RunNow, WriteStorage},
     input::{is_close_requested, is_key_down, InputEvent, VirtualKeyCode},
     renderer::sprite::SpriteRender,
     StateData, Trans,
@@ -17,9 +18,11 @@ use amethyst::{GameData, SimpleState, SimpleTrans};
 
 use dsf_precompile::AnimationId;
 
+use crate::components::Dying;
 use crate::entities::*;
 use crate::levels::*;
 use crate::resources::*;
+use crate::simulation::{build_ghost, spawn_best_run_check, update_ghost, Ghost};
 use crate::states::window_event_handler;
 use crate::systems;
 use crate::systems::SoundEvent;
@@ -27,10 +30,71 @@ use crate::utility::files::get_levels_dir;
 use amethyst::core::ecs::{Dispatcher, DispatcherBuilder};
 use amethyst::core::shrev::EventChannel;
 use amethyst::core::SystemExt;
+use amethyst::prelude::Config;
+
+/// Builds the dispatcher that runs while the PlayState is active: the game rules, surrounded by the
+/// systems that only the game itself needs, such as recording input, rewinding and effects that
+/// are only shown on screen.
+pub fn build_play_dispatcher() -> Dispatcher<'static, 'static> {
+    let builder = DispatcherBuilder::new()
+        .with(
+            systems::HistorySystem::default().pausable(CurrentState::Running),
+            "history_system",
+            &[],
+        )
+        .with(
+            systems::InputSnapshotSystem.pausable(CurrentState::Running),
+            "input_snapshot_system",
+            &["history_system"],
+        )
+        .with(
+            systems::RewindControlSystem,
+            "rewind_control_system",
+            &["input_snapshot_system"],
+        )
+        .with(
+            systems::RewindSystem.pausable(CurrentState::Rewinding),
+            "rewind_system",
+            &["rewind_control_system"],
+        );
+    systems::add_game_rules(builder, &["rewind_control_system"])
+        .with(systems::DebugSystem, "debug_system", &["death_system"])
+        .with(
+            systems::HeldKeysDisplaySystem::default(),
+            "held_keys_display_system",
+            &["death_system"],
+        )
+        .with(
+            systems::BlastPreviewSystem::default(),
+            "blast_preview_system",
+            &["death_system"],
+        )
+        .with(
+            systems::DeathAnimationSystem.pausable(CurrentState::Running),
+            "death_animation_system",
+            &["death_system"],
+        )
+        .build()
+}
 
 pub struct PlayState {
     dispatcher: Dispatcher<'static, 'static>,
     level_file: PathBuf,
+    /// If this is set, the state is in replay mode: the recorded input is fed to the game instead
+    /// of the keyboard input.
+    replay: Option<Replay>,
+    /// If this is true, the level's save is resumed on start, and the player's progress is saved
+    /// when they leave the level unfinished.
+    resumable: bool,
+    /// Replays the player's best run of the level next to them, if they completed it before.
+    /// There is no ghost in replay mode.
+    ghost: Option<Ghost>,
+    /// Set if the level could not be loaded. The state then pops itself on the next update, back
+    /// to the state that pushed it.
+    failed_to_load: bool,
+    /// Completed runs that are being checked in the background, to see if they are the player's
+    /// new best run. They are waited for when the state stops.
+    best_run_checks: Vec<JoinHandle<()>>,
 }
 
 impl<'a, 'b> PlayState {
@@ -40,53 +104,40 @@ impl<'a, 'b> PlayState {
         PlayState::new(level_file)
     }
 
+    /// Creates a PlayState in replay mode. It loads the level that the replay was recorded on and
+    /// plays back the recorded input instead of listening to the keyboard.
+    pub fn replay(replay: Replay) -> Self {
+        let mut state = PlayState::new(replay.level_path());
+        state.replay = Some(replay);
+        state
+    }
+
+    /// Creates a PlayState for the given level that picks up where the player left off, if they
+    /// left the level unfinished before. When the player leaves the level unfinished again, their
+    /// progress is saved.
+    pub fn resumable(level_file: PathBuf) -> Self {
+        let mut state = PlayState::new(level_file);
+        state.resumable = true;
+        state
+    }
+
     /// Creates a new PlayState that will load the given level.
     pub fn new(level_file: PathBuf) -> Self {
         PlayState {
             level_file,
-            dispatcher: DispatcherBuilder::new()
-                .with(
-                    systems::PlayerSystem::default().pausable(CurrentState::Running),
-                    "player_system",
-                    &[],
-                )
-                .with(
-                    systems::SteeringSystem::default().pausable(CurrentState::Running),
-                    "steering_system",
-                    &["player_system"],
-                )
-                .with(
-                    systems::MovementSystem.pausable(CurrentState::Running),
-                    "movement_system",
-                    &["steering_system"],
-                )
-                .with(
-                    systems::VelocitySystem.pausable(CurrentState::Running),
-                    "velocity_system",
-                    &["movement_system"],
-                )
-                .with(
-                    systems::RewindControlSystem,
-                    "rewind_control_system",
-                    &["player_system"],
-                )
-                .with(
-                    systems::RewindSystem.pausable(CurrentState::Rewinding),
-                    "rewind_system",
-                    &["rewind_control_system"],
-                )
-                .with(systems::DebugSystem, "debug_system", &[])
-                .with(systems::KeyCollectionSystem, "key_collection_system", &[])
-                .with(systems::PickupSystem, "pickup_system", &[])
-                .with(systems::UseToolSystem, "use_tool_system", &[])
-                .with(systems::LevelWrappingSystem, "level_wrapping_system", &[])
-                .with(systems::WinSystem, "win_system", &[])
-                .build(),
+            replay: None,
+            resumable: false,
+            ghost: None,
+            failed_to_load: false,
+            best_run_checks: vec![],
+            dispatcher: build_play_dispatcher(),
         }
     }
 
     fn handle_action(&mut self, action: &str, world: &mut World) -> SimpleTrans {
-        if action == "speedUp" {
+        if self.failed_to_load {
+            Trans::None
+        } else if action == "speedUp" {
             let (old_scale, new_scale) = (*world.fetch_mut::<DebugSettings>()).increase_speed();
             info!("Speeding up time, from x{:?} to x{:?}. This feature exists for debugging purposes only.", old_scale, new_scale);
             self.update_time_scale(world, new_scale);
@@ -96,27 +147,257 @@ impl<'a, 'b> PlayState {
             info!("Slowing down time, from x{:?} to x{:?}. This feature exists for debugging purposes only.", old_scale, new_scale);
             self.update_time_scale(world, new_scale);
             Trans::None
+        } else if action == "undo" {
+            self.undo(world);
+            Trans::None
         } else if action == "restart" {
-            world
-                .write_resource::<EventChannel<SoundEvent>>()
-                .single_write(SoundEvent::new(SoundType::LvlReset));
-            self.reset_level(world);
+            self.restart(world);
             Trans::None
         } else {
             Trans::None
         }
     }
 
+    /// Puts the game back in the state it was in right before the player's last action.
+    /// Does nothing while the game is rewinding, rewinding already controls the game state then.
+    fn undo(&self, world: &mut World) {
+        if *world.read_resource::<CurrentState>() == CurrentState::Rewinding {
+            return;
+        }
+        let checkpoint = world.write_resource::<History>().pop_checkpoint();
+        if let Some(checkpoint) = checkpoint {
+            info!("Undoing last action, back to {:?}", checkpoint);
+            world.write_resource::<ReplayRecorder>().interrupt();
+            systems::restore_frame(world, &checkpoint);
+            world.write_resource::<History>().force_key_frame = true;
+        }
+    }
+
+    /// Brings the player back after they died, either at the start of the level or at the last
+    /// checkpoint, depending on the level.
+    fn respawn(&mut self, world: &mut World) {
+        let respawn = world.read_resource::<TileMap>().hazards.respawn;
+        let checkpoint = match respawn {
+            Respawn::LevelStart => None,
+            Respawn::LastCheckpoint => world.write_resource::<History>().pop_checkpoint(),
+        };
+        if let Some(checkpoint) = checkpoint {
+            info!("Respawning at the last checkpoint.");
+            world.write_resource::<ReplayRecorder>().interrupt();
+            systems::restore_frame(world, &checkpoint);
+            world.write_resource::<History>().force_key_frame = true;
+        } else {
+            info!("Respawning at the start of the level.");
+            self.save_replay(world);
+            self.reset_level(world);
+        }
+    }
+
     fn update_time_scale(&self, world: &mut World, time_scale: f32) {
         world.write_resource::<Time>().set_time_scale(time_scale);
     }
 
-    fn reset_level(&self, world: &mut World) {
+    fn restart(&mut self, world: &mut World) {
+        world
+            .write_resource::<EventChannel<SoundEvent>>()
+            .single_write(SoundEvent::new(SoundType::LvlReset));
+        self.save_replay(world);
+        self.report_exit(world);
+        self.reset_level(world);
+    }
+
+    fn reset_level(&mut self, world: &mut World) {
         world.delete_all();
         UiHandles::add_ui(&UiType::Fps, world);
         UiHandles::add_ui(&UiType::Play, world);
         create_camera(world);
-        load_level(&self.level_file, world).expect("Failed to load level!");
+        if let Err(err) = load_level(&self.level_file, world) {
+            error!("Failed to load level {:?}: {}", self.level_file, err);
+            world.insert(LevelSelectMessage {
+                text: err.to_string(),
+            });
+            self.failed_to_load = true;
+            return;
+        }
+        world.insert(ReplayRecorder::new(&self.level_file));
+        world.insert(
+            self.replay
+                .as_ref()
+                .map(ReplayPlayback::new)
+                .unwrap_or_default(),
+        );
+        self.ghost = if self.replay.is_none() {
+            let config = (*world.read_resource::<MovementConfig>()).clone();
+            Ghost::load(&self.level_file, config)
+        } else {
+            None
+        };
+        if let Some(ghost) = &self.ghost {
+            build_ghost(world, ghost);
+        }
+    }
+
+    /// Moves the ghost along with the game. The ghost stands still while the game is rewinding.
+    fn update_ghost(&mut self, world: &mut World) {
+        if let Some(ghost) = &mut self.ghost {
+            if *world.read_resource::<CurrentState>() == CurrentState::Running {
+                ghost.step();
+            }
+            update_ghost(world, ghost);
+        }
+    }
+
+    /// Puts the level back in the state saved when the player last left it, if there is a save.
+    fn resume_save(&mut self, world: &mut World) {
+        if let Some(save) = SaveGame::load_for_level(&self.level_file) {
+            info!(
+                "Resuming {:?} after {:.1} seconds of play.",
+                self.level_file, save.elapsed_seconds
+            );
+            systems::restore_frame(world, &save.frame);
+            if let Some(history) = save.history {
+                world.insert(history);
+            }
+            // The ghost catches up with the time the player already spent in the level.
+            if let Some(ghost) = &mut self.ghost {
+                ghost.skip(save.replay.nr_ticks());
+            }
+            world.write_resource::<ReplayRecorder>().replay = save.replay;
+        }
+    }
+
+    /// Saves the player's progress if they are leaving the level unfinished. If the player is
+    /// dying, the save holds the checkpoint they would respawn at. If the level is finished or
+    /// lost, or the death sends the player back to the start of the level, the save is deleted
+    /// instead: there is nothing left to resume.
+    fn save_progress(&self, world: &mut World) {
+        let (ended, killed_by) = {
+            let win = world.read_resource::<WinCondition>();
+            (win.reached_open_door || win.lost(), win.killed_by)
+        };
+        if ended {
+            SaveGame::delete(&self.level_file);
+        } else if let Some(cause) = killed_by {
+            match self.save_after_death(world, cause) {
+                Some(save) => save.save(&self.level_file),
+                None => SaveGame::delete(&self.level_file),
+            }
+        } else if let Some(save) = self.save_in_play(world) {
+            save.save(&self.level_file);
+        }
+    }
+
+    /// The current state of the game, for a player who leaves the level in the middle of play.
+    fn save_in_play(&self, world: &mut World) -> Option<SaveGame> {
+        // Record the current state of the game, even if it did not change since the last Frame.
+        world.write_resource::<History>().force_key_frame = true;
+        systems::HistorySystem::default().run_now(world);
+        let frame = world.read_resource::<History>().last_frame().cloned()?;
+        let history = (*world.read_resource::<History>()).clone();
+        let replay = world.read_resource::<ReplayRecorder>().replay.clone();
+        Some(self.save_game(world, frame, history, replay))
+    }
+
+    /// The checkpoint that a dying player would respawn at, for a player who leaves the level
+    /// during the death animation. Resuming a Frame from during the death would only kill them
+    /// again. Returns None if the player would not respawn at a checkpoint.
+    fn save_after_death(&self, world: &mut World, cause: DeathCause) -> Option<SaveGame> {
+        // Mirrors what happens once the death animation is over, see fixed_update and respawn.
+        let respawns_at_checkpoint = matches!(cause, DeathCause::Hazard | DeathCause::Fall)
+            && world.read_resource::<TileMap>().hazards.respawn == Respawn::LastCheckpoint;
+        if !respawns_at_checkpoint {
+            return None;
+        }
+        let mut history = (*world.read_resource::<History>()).clone();
+        let checkpoint = history.pop_checkpoint()?;
+        history.force_key_frame = true;
+        let mut replay = world.read_resource::<ReplayRecorder>().replay.clone();
+        // Respawning is not input, so the replay can no longer reproduce the attempt.
+        replay.interrupted = true;
+        Some(self.save_game(world, checkpoint, history, replay))
+    }
+
+    fn save_game(&self, world: &World, frame: Frame, history: History, replay: Replay) -> SaveGame {
+        SaveGame {
+            level_hash: replay.level_hash,
+            elapsed_seconds: replay.nr_ticks() as f32
+                * world.read_resource::<Time>().fixed_seconds(),
+            frame,
+            history: Some(history),
+            replay,
+        }
+    }
+
+    /// If the player finished the level, remembers which exit they took. The LevelSelectState
+    /// uses this to decide which roads on the adventure map are open. Nothing is reported in
+    /// replay mode.
+    fn report_exit(&self, world: &mut World) {
+        if self.replay.is_some() {
+            return;
+        }
+        let exit = world.read_resource::<WinCondition>().exit_taken.clone();
+        if let Some(exit) = exit {
+            let level_name = self
+                .level_file
+                .file_name()
+                .expect("This should not happen.")
+                .to_str()
+                .expect("Level file name did not contain valid unicode.")
+                .to_string();
+            world
+                .write_resource::<UserCache>()
+                .save_exit_taken(level_name, exit);
+        }
+    }
+
+    /// Writes the input recorded since the level was last (re)started to the replay file for this
+    /// level, overwriting the previous replay. Nothing is written in replay mode, or if the
+    /// attempt cannot be replayed.
+    fn save_replay(&self, world: &mut World) {
+        if self.replay.is_some() {
+            return;
+        }
+        let recorder = world.read_resource::<ReplayRecorder>();
+        if recorder.replay.is_empty() {
+            return;
+        }
+        if !recorder.replay.is_replayable() {
+            info!("Not saving the replay, this attempt was rewound, undone or respawned.");
+            return;
+        }
+        let replay_file = replay_file_for_level(&self.level_file);
+        match recorder.replay.write(&replay_file) {
+            Ok(()) => info!("Saved replay to {:?}", replay_file),
+            Err(err) => error!("Failed to save replay to {:?}: {:?}", replay_file, err),
+        }
+    }
+
+    /// Starts checking whether the run that just completed the level is the player's best run.
+    /// Called once per attempt, in the tick in which the recording of the attempt finished.
+    fn check_best_run(&mut self, world: &mut World) {
+        if self.replay.is_some() || !world.read_resource::<WinCondition>().reached_open_door {
+            return;
+        }
+        let replay = world.read_resource::<ReplayRecorder>().replay.clone();
+        if !replay.is_replayable() {
+            return;
+        }
+        let config = (*world.read_resource::<MovementConfig>()).clone();
+        self.best_run_checks.push(spawn_best_run_check(
+            self.level_file.clone(),
+            replay,
+            config,
+        ));
+    }
+
+    /// Waits for the background checks of completed runs, so their best runs are saved before
+    /// the game moves on.
+    fn finish_best_run_checks(&mut self) {
+        for check in self.best_run_checks.drain(..) {
+            if check.join().is_err() {
+                error!("Checking a completed run for a new best run failed.");
+            }
+        }
     }
 }
 
@@ -125,10 +406,27 @@ impl SimpleState for PlayState {
         info!("PlayState on_start");
         self.dispatcher.setup(data.world);
         self.reset_level(data.world);
+        if self.failed_to_load {
+            return;
+        }
+        if self.resumable {
+            self.resume_save(data.world);
+        }
+        let track = data.world.read_resource::<TileMap>().metadata.music.clone();
+        prefer_music(data.world, track.as_deref());
     }
 
     fn on_stop(&mut self, data: StateData<GameData>) {
         info!("PlayState on_stop");
+        if !self.failed_to_load {
+            if self.resumable {
+                self.save_progress(data.world);
+            }
+            self.save_replay(data.world);
+            self.report_exit(data.world);
+        }
+        self.finish_best_run_checks();
+        prefer_music(data.world, None);
         data.world.delete_all();
     }
 
@@ -158,11 +456,36 @@ impl SimpleState for PlayState {
     }
 
     fn fixed_update(&mut self, data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if self.failed_to_load {
+            return Trans::Pop;
+        }
+        let was_recording = !data.world.read_resource::<ReplayRecorder>().finished;
         self.dispatcher.dispatch(&data.world);
+        if was_recording && data.world.read_resource::<ReplayRecorder>().finished {
+            self.check_best_run(data.world);
+        }
+        self.update_ghost(data.world);
+        let death_finished = data
+            .world
+            .exec(|dyings: ReadStorage<Dying>| dyings.join().any(|dying| dying.is_finished()));
+        if death_finished {
+            let killed_by = data.world.read_resource::<WinCondition>().killed_by;
+            match killed_by {
+                Some(DeathCause::Hazard) | Some(DeathCause::Fall) => self.respawn(data.world),
+                Some(DeathCause::Mob(ContactRule::ResetLevel)) | Some(DeathCause::LeftLevel) => {
+                    self.restart(data.world)
+                }
+                // The player lost the level, and stays dead until they restart it.
+                Some(DeathCause::Mob(ContactRule::LoseLevel)) | None => (),
+            }
+        }
         Trans::None
     }
 
     fn update(&mut self, data: &mut StateData<GameData>) -> SimpleTrans {
+        if self.failed_to_load {
+            return Trans::Pop;
+        }
         // Execute a pass similar to a system
         data.world.exec(
             #[allow(clippy::type_complexity)]
@@ -1,6 +1,8 @@
 use crate::components::{Direction2D, MapCursor};
+use crate::levels::LevelMetadata;
 use crate::resources::{
-    Adventure, AdventureNode, MapElement, MovementConfig, NodeDetails, PositionOnMap, SoundType,
+    Adventure, AdventureNode, LevelSelectMessage, MapElement, MovementConfig, NodeDetails,
+    PositionOnMap, SoundType,
 };
 use crate::systems::SoundEvent;
 use amethyst::core::ecs::{Join, Read, System, Write, WriteStorage};
@@ -98,8 +100,8 @@ fn move_cursor(
     }
 }
 
-/// Updates the UI label on the adventure and level select screen. The label must always display the
-/// name of the currently selected node.
+/// Updates the UI labels on the adventure and level select screen. The labels must always display
+/// the name of the currently selected node, and the details of the level if the node is a level.
 pub struct LevelSelectUiUpdateSystem;
 
 impl<'s> System<'s> for LevelSelectUiUpdateSystem {
@@ -109,27 +111,66 @@ impl<'s> System<'s> for LevelSelectUiUpdateSystem {
         UiFinder<'s>,
         Read<'s, Adventure>,
         Read<'s, PositionOnMap>,
+        Read<'s, LevelSelectMessage>,
     );
 
-    fn run(&mut self, (mut ui_text, finder, adventure, pos_on_map): Self::SystemData) {
-        let label_title = {
-            let label_title_entity = finder.find("label_node_title");
-            if let Some(fps_entity) = label_title_entity {
-                ui_text.get_mut(fps_entity)
-            } else {
-                None
-            }
+    fn run(&mut self, (mut ui_text, finder, adventure, pos_on_map, message): Self::SystemData) {
+        let selected = adventure.nodes.get(&pos_on_map.pos);
+        let (file_name, metadata) = match selected {
+            Some(MapElement::Node(AdventureNode {
+                details: NodeDetails::Level(file_name),
+                ..
+            })) => (Some(file_name), adventure.level_metadata.get(file_name)),
+            _ => (None, None),
         };
-        if let Some(mut label_title) = label_title {
-            let selected = adventure.nodes.get(&pos_on_map.pos);
-            let selected_title = match selected {
-                Some(MapElement::Node(AdventureNode {
-                    details: NodeDetails::Level(file_name),
-                    ..
-                })) => file_name,
-                _ => "Nothing",
-            };
-            label_title.text = format!("Selected: {:?}", selected_title);
+        let title = metadata
+            .and_then(|metadata| metadata.title.as_ref())
+            .or(file_name)
+            .map(|title| title.as_str())
+            .unwrap_or("Nothing");
+        let texts = [
+            ("label_node_title", format!("Selected: {}", title)),
+            (
+                "label_node_details",
+                metadata.map(format_details).unwrap_or_default(),
+            ),
+            (
+                "label_node_description",
+                metadata
+                    .and_then(|metadata| metadata.description.clone())
+                    .unwrap_or_default(),
+            ),
+            ("label_message", message.text.clone()),
+        ];
+        for (id, text) in texts.iter() {
+            if let Some(label) = finder.find(id).and_then(|entity| ui_text.get_mut(entity)) {
+                label.text = text.clone();
+            }
         }
     }
 }
+
+/// Puts the author, difficulty and par of a level on a single line. Details that the level does
+/// not have are left out.
+fn format_details(metadata: &LevelMetadata) -> String {
+    let par = match (metadata.par_seconds, metadata.par_moves) {
+        (Some(seconds), Some(moves)) => Some(format!("Par: {:.0}s, {} moves", seconds, moves)),
+        (Some(seconds), None) => Some(format!("Par: {:.0}s", seconds)),
+        (None, Some(moves)) => Some(format!("Par: {} moves", moves)),
+        (None, None) => None,
+    };
+    let details: Vec<String> = vec![
+        metadata
+            .author
+            .as_ref()
+            .map(|author| format!("By {}", author)),
+        metadata
+            .difficulty
+            .map(|difficulty| format!("Difficulty: {:?}", difficulty)),
+        par,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    details.join(" | ")
+}
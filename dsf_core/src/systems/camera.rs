@@ -1,4 +1,6 @@
 use crate::components::*;
+use crate::levels::EdgeRule;
+use crate::resources::TileMap;
 
 use amethyst::{
     core::transform::Transform,
@@ -55,6 +57,9 @@ impl<'s> System<'s> for CameraControlSystem {
 }
 
 /// This system updates the camera frame position to center on the player's position.
+///
+/// Along an axis where the level does not wrap, the camera does not follow the player beyond the
+/// borders of the level.
 pub struct CameraSystem;
 
 impl<'s> System<'s> for CameraSystem {
@@ -62,14 +67,27 @@ impl<'s> System<'s> for CameraSystem {
         ReadStorage<'s, Player>,
         ReadStorage<'s, CameraFrame>,
         WriteStorage<'s, Transform>,
+        Read<'s, TileMap>,
     );
 
-    fn run(&mut self, (players, camera_frames, mut transforms): Self::SystemData) {
+    fn run(&mut self, (players, camera_frames, mut transforms, tile_map): Self::SystemData) {
         let maybe_player_pos = (&players, &transforms)
             .join()
             .map(|(_, transform)| (transform.translation().x, transform.translation().y))
             .next();
         if let Some((player_x, player_y)) = maybe_player_pos {
+            let player_x = clamp_to_level(
+                player_x,
+                tile_map.pos.x,
+                tile_map.dimens.x,
+                tile_map.wrapping.x,
+            );
+            let player_y = clamp_to_level(
+                player_y,
+                tile_map.pos.y,
+                tile_map.dimens.y,
+                tile_map.wrapping.y,
+            );
             for (frame, transform) in (&camera_frames, &mut transforms).join() {
                 transform.set_translation_x(player_x + frame.pan.x);
                 transform.set_translation_y(player_y + frame.pan.y);
@@ -77,3 +95,14 @@ impl<'s> System<'s> for CameraSystem {
         }
     }
 }
+
+/// Clamps the coordinate to the level along one axis, unless the level wraps along that axis.
+fn clamp_to_level(coordinate: f32, level_pos: i32, level_dimens: i32, edge_rule: EdgeRule) -> f32 {
+    if edge_rule == EdgeRule::Wrap {
+        coordinate
+    } else {
+        coordinate
+            .max(level_pos as f32)
+            .min((level_pos + level_dimens) as f32)
+    }
+}
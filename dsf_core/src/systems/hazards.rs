@@ -0,0 +1,155 @@
+use crate::components::*;
+use crate::levels::*;
+use crate::resources::*;
+use crate::systems::SoundEvent;
+use amethyst::core::ecs::shrev::EventChannel;
+use amethyst::core::ecs::{Entities, LazyUpdate};
+use amethyst::{
+    core::math::Vector3,
+    core::timing::Time,
+    core::transform::Transform,
+    ecs::prelude::{Join, Read, ReadStorage, System, Write, WriteStorage},
+};
+
+/// How fast the player spins during the death animation, in radians per second.
+const DEATH_SPIN_SPEED: f32 = 12.;
+
+/// Broadcast whenever the player dies. Other systems can listen to these, for instance to count
+/// how often the player died in a level.
+#[derive(Debug, Clone)]
+pub struct DeathEvent {
+    pub cause: DeathCause,
+}
+
+impl DeathEvent {
+    pub fn new(cause: DeathCause) -> Self {
+        DeathEvent { cause }
+    }
+}
+
+/// Kills the player when they touch a hazard tile, or when they land after falling further than
+/// the level allows. The DeathSystem takes it from there.
+///
+/// The fall height is read from the player's Steering, which is rewound and saved along with the
+/// rest of the game. This system keeps no state of its own.
+pub struct HazardSystem;
+
+impl<'s> System<'s> for HazardSystem {
+    type SystemData = (
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, Steering>,
+        Read<'s, TileMap>,
+        Write<'s, WinCondition>,
+    );
+
+    fn run(&mut self, (players, steerings, tile_map, mut win): Self::SystemData) {
+        if win.is_dying() {
+            return;
+        }
+        for (_, steering) in (&players, &steerings).join() {
+            let fell_too_far = match (steering.landed_after_fall, tile_map.hazards.max_safe_fall) {
+                (Some(fall_height), Some(max_safe_fall)) => fall_height > max_safe_fall,
+                _ => false,
+            };
+            if touches_hazard(steering, &tile_map) {
+                win.killed_by = Some(DeathCause::Hazard);
+            } else if fell_too_far {
+                win.killed_by = Some(DeathCause::Fall);
+            }
+        }
+    }
+}
+
+/// Starts the death of the player once something killed them, whatever it was: announces the
+/// death and starts the death animation. Systems that can kill the player only record the cause
+/// in the WinCondition, and leave the rest to this system.
+pub struct DeathSystem;
+
+impl<'s> System<'s> for DeathSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, Transform>,
+        WriteStorage<'s, Dying>,
+        Read<'s, WinCondition>,
+        Write<'s, EventChannel<DeathEvent>>,
+        Write<'s, EventChannel<SoundEvent>>,
+        Read<'s, LazyUpdate>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            players,
+            transforms,
+            mut dyings,
+            win,
+            mut death_channel,
+            mut sound_channel,
+            lazy,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let cause = match win.killed_by {
+            Some(cause) => cause,
+            None => return,
+        };
+        let newly_dead = (&players, &transforms, &entities, !&dyings)
+            .join()
+            .map(|(_, transform, entity, _)| {
+                let scale = transform.scale();
+                (entity, Dying::new((scale.x, scale.y, scale.z)))
+            })
+            .collect::<Vec<_>>();
+        for (entity, dying) in newly_dead {
+            info!("Player died: {:?}", cause);
+            dyings
+                .insert(entity, dying)
+                .expect("Failed to mark player as dying.");
+            death_channel.single_write(DeathEvent::new(cause));
+            sound_channel.single_write(SoundEvent::new(SoundType::Death));
+            if win.lost() {
+                lazy.exec_mut(move |world| {
+                    UiHandles::add_ui(&UiType::LoseMessage, world);
+                });
+            }
+        }
+    }
+}
+
+/// Plays the death animation: the player spins and shrinks until they disappear.
+pub struct DeathAnimationSystem;
+
+impl<'s> System<'s> for DeathAnimationSystem {
+    type SystemData = (
+        WriteStorage<'s, Dying>,
+        WriteStorage<'s, Transform>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (mut dyings, mut transforms, time): Self::SystemData) {
+        for (dying, transform) in (&mut dyings, &mut transforms).join() {
+            dying.elapsed += time.fixed_seconds();
+            let remaining = (1. - dying.elapsed / Dying::DURATION).max(0.);
+            transform.set_scale(Vector3::new(
+                dying.scale.0 * remaining,
+                dying.scale.1 * remaining,
+                dying.scale.2,
+            ));
+            transform.set_rotation_2d(dying.elapsed * DEATH_SPIN_SPEED);
+        }
+    }
+}
+
+/// True iff the entity with the given steering overlaps a hazard tile.
+fn touches_hazard(steering: &Steering, tile_map: &TileMap) -> bool {
+    (0..steering.dimens.x).any(|x| {
+        (0..steering.dimens.y).any(|y| {
+            tile_map
+                .get_tile(&steering.pos.append_xy(x, y))
+                .map(|tile| tile.archetype == Archetype::Hazard)
+                .unwrap_or(false)
+        })
+    })
+}
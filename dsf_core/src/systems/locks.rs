@@ -0,0 +1,161 @@
+use amethyst::core::ecs::{Entities, LazyUpdate};
+use amethyst::{
+    core::math::{Vector2, Vector3},
+    core::transform::Transform,
+    core::Parent,
+    ecs::prelude::{Entity, Join, Read, ReadStorage, System, World, Write},
+    prelude::{Builder, WorldExt},
+};
+
+use crate::components::*;
+use crate::levels::*;
+use crate::resources::*;
+use crate::systems::SoundEvent;
+use amethyst::core::ecs::shrev::EventChannel;
+use std::collections::BTreeSet;
+
+/// Coloured key width and height, hardcoded for now.
+/// TODO: Get rid of these hardcoded constants.
+const KEY_WIDTH: f32 = 2.;
+const KEY_HEIGHT: f32 = 2.;
+
+/// Checks if the player intersects any coloured keys. If so, the key is collected and all locks
+/// of the same colour are removed from the game.
+pub struct ColouredKeySystem;
+
+impl<'s> System<'s> for ColouredKeySystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Write<'s, EventChannel<SoundEvent>>,
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, Steering>,
+        ReadStorage<'s, ColouredKey>,
+        ReadStorage<'s, Lock>,
+        ReadStorage<'s, Block>,
+        ReadStorage<'s, Transform>,
+        Write<'s, KeyRing>,
+        Write<'s, TileMap>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut sound_channel,
+            players,
+            steerings,
+            keys,
+            locks,
+            blocks,
+            transforms,
+            mut key_ring,
+            mut tile_map,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let player_collider = (&players, &steerings, &transforms)
+            .join()
+            .map(|(_, steering, transform)| {
+                (
+                    Vector2::new(transform.translation().x, transform.translation().y),
+                    Vector2::new(steering.dimens.x as f32, steering.dimens.y as f32),
+                )
+            })
+            .next();
+        if let Some((pos, dimens)) = player_collider {
+            let collected_key = (&keys, &transforms, &entities)
+                .join()
+                .filter(|(_, transform, _)| {
+                    let key_x = transform.translation().x;
+                    let key_y = transform.translation().y;
+                    pos.x - dimens.x / 2. < key_x + KEY_WIDTH / 3.
+                        && pos.x + dimens.x / 2. > key_x - KEY_WIDTH / 3.
+                        && pos.y - dimens.y / 2. < key_y + KEY_HEIGHT / 3.
+                        && pos.y + dimens.y / 2. > key_y - KEY_HEIGHT / 3.
+                })
+                .map(|(key, _, entity)| (*key, entity))
+                .next();
+            if let Some((key, key_entity)) = collected_key {
+                sound_channel.single_write(SoundEvent::new(SoundType::KeyPickup));
+                key_ring.held.insert(key.colour);
+                tile_map.remove_whole_tile(&key.pos);
+                entities.delete(key_entity).expect("Failed to delete key.");
+                for (lock, block, entity) in (&locks, &blocks, &entities).join() {
+                    if lock.colour == key.colour {
+                        tile_map.remove_whole_tile(&block.pos);
+                        entities.delete(entity).expect("Failed to delete lock.");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Shows the coloured keys that the player holds, as small keys floating above their head.
+#[derive(Default)]
+pub struct HeldKeysDisplaySystem {
+    /// The colours that are currently shown.
+    shown: BTreeSet<KeyColour>,
+}
+
+impl<'s> System<'s> for HeldKeysDisplaySystem {
+    type SystemData = (Read<'s, KeyRing>, Read<'s, LazyUpdate>);
+
+    fn run(&mut self, (key_ring, lazy): Self::SystemData) {
+        if key_ring.held != self.shown {
+            self.shown = key_ring.held.clone();
+            lazy.exec_mut(show_held_keys);
+        }
+    }
+}
+
+/// Replaces the displays of the held keys with displays for the keys in the KeyRing.
+pub fn show_held_keys(world: &mut World) {
+    let (old_displays, player) = world.exec(
+        |(entities, displays, players): (
+            Entities,
+            ReadStorage<HeldKeyDisplay>,
+            ReadStorage<Player>,
+        )| {
+            let old_displays = (&entities, &displays)
+                .join()
+                .map(|(entity, _)| entity)
+                .collect::<Vec<Entity>>();
+            let player = (&entities, &players)
+                .join()
+                .map(|(entity, _)| entity)
+                .next();
+            (old_displays, player)
+        },
+    );
+    if let Err(err) = world.delete_entities(&old_displays) {
+        error!("Failed to delete held key displays: {:?}", err);
+    }
+    // A headless simulation has no assets, there is nothing to show there.
+    let player = match player {
+        Some(player) if world.has_value::<Assets>() => player,
+        _ => return,
+    };
+    let held = world
+        .read_resource::<KeyRing>()
+        .held
+        .iter()
+        .copied()
+        .collect::<Vec<KeyColour>>();
+    for (index, colour) in held.iter().enumerate() {
+        let mut transform = Transform::default();
+        transform.set_translation_x((index as f32 - (held.len() - 1) as f32 / 2.) * 32.);
+        transform.set_translation_y(96.);
+        transform.set_translation_z(1.); //One higher than parent.
+        transform.set_scale(Vector3::new(0.25, 0.25, 1.0));
+        let sprite = load_asset_from_world(&SpriteType::Blocks, 3, world);
+        world
+            .create_entity()
+            .with(HeldKeyDisplay)
+            .with(transform)
+            .with(sprite)
+            .with(colour_tint(*colour))
+            .with(Parent { entity: player })
+            .build();
+    }
+}
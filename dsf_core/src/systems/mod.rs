@@ -3,9 +3,13 @@ mod audio;
 mod camera;
 mod debug;
 mod fps_counter;
+mod hazards;
+mod locks;
 mod motion;
 mod rewind;
+mod rules;
 mod tools;
+mod triggers;
 mod win;
 
 pub use self::adventure::*;
@@ -13,7 +17,11 @@ pub use self::audio::*;
 pub use self::camera::*;
 pub use self::debug::*;
 pub use self::fps_counter::*;
+pub use self::hazards::*;
+pub use self::locks::*;
 pub use self::motion::*;
 pub use self::rewind::*;
+pub use self::rules::*;
 pub use self::tools::*;
+pub use self::triggers::*;
 pub use self::win::*;
@@ -1,57 +1,88 @@
-use crate::components::{Steering, SteeringMode};
-use crate::resources::TileMap;
-use amethyst::core::ecs::{Join, Read, System, WriteStorage};
+use crate::components::{Player, Pos, Steering, SteeringMode};
+use crate::levels::EdgeRule;
+use crate::resources::{DeathCause, TileMap, WinCondition};
+use amethyst::core::ecs::{Join, Read, ReadStorage, System, Write, WriteStorage};
 use amethyst::core::Transform;
 
 /// Implements wrapping behaviour for levels.
 ///
 /// IE: if character falls out the bottom, they appear at top. If character goes off to the left,
 /// they wrap around to the right.
+///
+/// Whether this happens is decided per axis by the level's wrapping settings. If the borders of an
+/// axis act as walls, nothing can leave the level that way. If leaving the level resets it, the
+/// player dies when they leave the level.
 pub struct LevelWrappingSystem;
 
 impl<'s> System<'s> for LevelWrappingSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
+        ReadStorage<'s, Player>,
         WriteStorage<'s, Steering>,
         WriteStorage<'s, Transform>,
         Read<'s, TileMap>,
+        Write<'s, WinCondition>,
     );
 
-    fn run(&mut self, (mut steerings, mut transforms, tile_map): Self::SystemData) {
-        for (transform, steering) in (&mut transforms, &mut steerings).join() {
-            if transform.translation().x < tile_map.pos.x as f32 {
-                transform.set_translation_x(transform.translation().x + tile_map.dimens.x as f32);
-                steering.pos.x += tile_map.dimens.x;
-                steering.destination.x += tile_map.dimens.x;
-            } else if transform.translation().x > (tile_map.pos.x + tile_map.dimens.x) as f32 {
-                transform.set_translation_x(transform.translation().x - tile_map.dimens.x as f32);
-                steering.pos.x -= tile_map.dimens.x;
-                steering.destination.x -= tile_map.dimens.x;
+    fn run(
+        &mut self,
+        (players, mut steerings, mut transforms, tile_map, mut win): Self::SystemData,
+    ) {
+        for (transform, steering, player) in
+            (&mut transforms, &mut steerings, players.maybe()).join()
+        {
+            let left_x = transform.translation().x < tile_map.pos.x as f32
+                || transform.translation().x > (tile_map.pos.x + tile_map.dimens.x) as f32;
+            let left_y = transform.translation().y < tile_map.pos.y as f32
+                || transform.translation().y > (tile_map.pos.y + tile_map.dimens.y) as f32;
+            if player.is_some()
+                && ((left_x && tile_map.wrapping.x == EdgeRule::ResetLevel)
+                    || (left_y && tile_map.wrapping.y == EdgeRule::ResetLevel))
+            {
+                if !win.is_dying() {
+                    win.killed_by = Some(DeathCause::LeftLevel);
+                }
+                continue;
             }
 
-            if transform.translation().y < tile_map.pos.y as f32 {
-                transform.set_translation_y(transform.translation().y + tile_map.dimens.y as f32);
-                steering.pos.y += tile_map.dimens.y;
-                steering.destination.y += tile_map.dimens.y;
-                // Ignore warning, we'll want to add more patterns in the future.
-                #[allow(clippy::single_match)]
-                match steering.mode {
-                    SteeringMode::Falling {
-                        x_movement,
-                        starting_y_pos,
-                        duration,
-                    } => {
-                        steering.mode = SteeringMode::Falling {
-                            x_movement,
-                            starting_y_pos: starting_y_pos + tile_map.dimens.y as f32,
-                            duration,
-                        }
-                    }
-                    _ => (),
-                };
-            } else if transform.translation().y > (tile_map.pos.y + tile_map.dimens.y) as f32 {
-                steering.pos.y -= tile_map.dimens.y;
-                steering.destination.y -= tile_map.dimens.y;
+            let mut offset = Pos::default();
+            if tile_map.wrapping.x != EdgeRule::Wall {
+                if transform.translation().x < tile_map.pos.x as f32 {
+                    offset.x = tile_map.dimens.x;
+                } else if transform.translation().x > (tile_map.pos.x + tile_map.dimens.x) as f32 {
+                    offset.x = -tile_map.dimens.x;
+                }
+            }
+            if tile_map.wrapping.y != EdgeRule::Wall {
+                if transform.translation().y < tile_map.pos.y as f32 {
+                    offset.y = tile_map.dimens.y;
+                } else if transform.translation().y > (tile_map.pos.y + tile_map.dimens.y) as f32 {
+                    offset.y = -tile_map.dimens.y;
+                }
             }
+            if offset != Pos::default() {
+                reposition(steering, transform, &offset);
+            }
+        }
+    }
+}
+
+/// Moves an entity by the given offset, without disturbing its movement: its discrete position,
+/// destination and Transform are all shifted, and a jump or fall in progress simply carries on
+/// from the new position.
+///
+/// Use this whenever an entity is moved instantly, such as when it wraps around the level or goes
+/// through a teleporter.
+pub fn reposition(steering: &mut Steering, transform: &mut Transform, offset: &Pos) {
+    steering.pos = steering.pos.append_xy(offset.x, offset.y);
+    steering.destination = steering.destination.append_xy(offset.x, offset.y);
+    transform.set_translation_x(transform.translation().x + offset.x as f32);
+    transform.set_translation_y(transform.translation().y + offset.y as f32);
+    match &mut steering.mode {
+        SteeringMode::Falling { starting_y_pos, .. }
+        | SteeringMode::Jumping { starting_y_pos, .. } => {
+            *starting_y_pos += offset.y as f32;
         }
+        _ => (),
     }
 }
@@ -0,0 +1,109 @@
+use crate::components::{Direction1D, Mob, Steering, SteeringIntent, SteeringMode};
+use crate::levels::MobBehaviour;
+use crate::resources::TileMap;
+use crate::systems::{
+    aligned_with_grid, can_climb_down, can_climb_up, is_against_wall_left, is_against_wall_right,
+};
+use amethyst::core::ecs::{Join, Read, ReadStorage, System, WriteStorage};
+use amethyst::core::Transform;
+
+/// Sets the intention to move for all mobs. This is the mob equivalent of the PlayerSystem:
+/// instead of reading the input, it decides where to go based on the mob's behaviour.
+pub struct MobSystem;
+
+impl<'s> System<'s> for MobSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'s, Mob>,
+        ReadStorage<'s, Steering>,
+        ReadStorage<'s, Transform>,
+        WriteStorage<'s, SteeringIntent>,
+        Read<'s, TileMap>,
+    );
+
+    fn run(
+        &mut self,
+        (mut mobs, steerings, transforms, mut steering_intents, tile_map): Self::SystemData,
+    ) {
+        for (mob, steering, transform, intent) in
+            (&mut mobs, &steerings, &transforms, &mut steering_intents).join()
+        {
+            let (anchored_x, anchored_y) = steering.to_anchor_coords(transform);
+            if mob.walk.is_neutral() {
+                mob.walk = Direction1D::Positive;
+            }
+            if mob.left_ladder_at != Some(steering.pos) {
+                mob.left_ladder_at = None;
+            }
+            intent.face = Direction1D::Neutral;
+            intent.jump = false;
+            intent.jump_direction = Direction1D::Neutral;
+            intent.walk_invalidated = false;
+            intent.walk = Direction1D::Neutral;
+            intent.climb = Direction1D::Neutral;
+            match steering.mode {
+                SteeringMode::Grounded => {
+                    if aligned_with_grid(steering.destination.x as f32, anchored_x, mob.walk)
+                        && is_against_wall(mob.walk, steering, &tile_map)
+                    {
+                        mob.walk = mob.walk.opposite();
+                    }
+                    intent.walk = mob.walk;
+                    if mob.behaviour == MobBehaviour::Climber && mob.left_ladder_at.is_none() {
+                        intent.climb = climb_direction(mob.climb, steering, &tile_map)
+                            .or_else(|| climb_direction(mob.climb.opposite(), steering, &tile_map))
+                            .unwrap_or(Direction1D::Neutral);
+                        if !intent.climb.is_neutral() {
+                            mob.climb = intent.climb;
+                        }
+                    }
+                }
+                SteeringMode::Climbing => {
+                    let aligned = aligned_with_grid(
+                        steering.destination.y as f32,
+                        anchored_y,
+                        Direction1D::Neutral,
+                    );
+                    if aligned && climb_direction(mob.climb, steering, &tile_map).is_none() {
+                        // Reached the end of the ladder, step off and continue patrolling.
+                        mob.left_ladder_at = Some(steering.pos);
+                        mob.climb = mob.climb.opposite();
+                        if is_against_wall(mob.walk, steering, &tile_map) {
+                            mob.walk = mob.walk.opposite();
+                        }
+                        intent.walk = mob.walk;
+                    } else {
+                        intent.climb = mob.climb;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Returns the given direction if the mob can climb that way from where it stands.
+fn climb_direction(
+    direction: Direction1D,
+    steering: &Steering,
+    tile_map: &TileMap,
+) -> Option<Direction1D> {
+    let can_climb = match direction {
+        Direction1D::Positive => can_climb_up(steering, tile_map),
+        Direction1D::Negative => can_climb_down(steering, tile_map),
+        Direction1D::Neutral => false,
+    };
+    if can_climb {
+        Some(direction)
+    } else {
+        None
+    }
+}
+
+fn is_against_wall(direction: Direction1D, steering: &Steering, tile_map: &TileMap) -> bool {
+    match direction {
+        Direction1D::Positive => is_against_wall_right(steering, steering.pos.y as f32, tile_map),
+        Direction1D::Negative => is_against_wall_left(steering, steering.pos.y as f32, tile_map),
+        Direction1D::Neutral => false,
+    }
+}
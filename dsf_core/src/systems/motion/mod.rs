@@ -1,9 +1,15 @@
 mod level_wrapping;
+mod mob;
 mod movement;
 mod player;
+mod push;
 mod steering;
+mod teleport;
 
 pub use self::level_wrapping::*;
+pub use self::mob::*;
 pub use self::movement::*;
 pub use self::player::*;
+pub use self::push::*;
 pub use self::steering::*;
+pub use self::teleport::*;
@@ -1,9 +1,54 @@
 use crate::components::{Direction1D, Player, Steering, SteeringIntent};
-use crate::resources::MovementConfig;
-use amethyst::core::ecs::{Join, Read, ReadStorage, System, WriteStorage};
+use crate::resources::{
+    InputSnapshot, MovementConfig, ReplayPlayback, ReplayRecorder, WinCondition,
+};
+use amethyst::core::ecs::{Join, Read, ReadStorage, System, Write, WriteStorage};
 use amethyst::core::Time;
 use amethyst::input::{InputHandler, StringBindings};
 
+/// Fills the InputSnapshot resource with the input for this tick, which is the only input that the
+/// game play systems read.
+///
+/// Normally the input comes from the keyboard. If a replay is being played back, the input comes
+/// from the replay instead. Either way, the input is recorded so it can be saved as a replay.
+pub struct InputSnapshotSystem;
+
+impl<'s> System<'s> for InputSnapshotSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Write<'s, InputSnapshot>,
+        Write<'s, ReplayPlayback>,
+        Write<'s, ReplayRecorder>,
+        Read<'s, WinCondition>,
+        Read<'s, InputHandler<StringBindings>>,
+    );
+
+    fn run(&mut self, (mut snapshot, mut playback, mut recorder, win, input): Self::SystemData) {
+        let next_input = if win.lost() {
+            // The player can no longer move after losing the level.
+            InputSnapshot::default()
+        } else if playback.is_active() {
+            playback.next_input()
+        } else {
+            InputSnapshot::new(
+                input.axis_value("move_x").unwrap_or(0.0),
+                input.axis_value("move_y").unwrap_or(0.0),
+                input.action_is_down("jump").unwrap_or(false),
+            )
+        };
+        // A dying player cannot move either. The replay is still read, so it stays in sync.
+        *snapshot = if win.is_dying() {
+            InputSnapshot::default()
+        } else {
+            next_input
+        };
+        recorder.record(*snapshot);
+        if win.reached_open_door || win.lost() {
+            recorder.finished = true;
+        }
+    }
+}
+
 /// Sets the player intention to move.
 #[derive(Default)]
 pub struct PlayerSystem;
@@ -14,7 +59,7 @@ impl<'s> System<'s> for PlayerSystem {
         WriteStorage<'s, Player>,
         ReadStorage<'s, Steering>,
         WriteStorage<'s, SteeringIntent>,
-        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, InputSnapshot>,
         Read<'s, MovementConfig>,
         Read<'s, Time>,
     );
@@ -23,9 +68,9 @@ impl<'s> System<'s> for PlayerSystem {
         &mut self,
         (mut players, steerings, mut steering_intents, input, config, time): Self::SystemData,
     ) {
-        let input_x = input.axis_value("move_x").unwrap_or(0.0);
-        let input_y = input.axis_value("move_y").unwrap_or(0.0);
-        let jump_down = input.action_is_down("jump").unwrap_or(false);
+        let input_x = input.move_x;
+        let input_y = input.move_y;
+        let jump_down = input.jump;
         for (player, intent, steering) in (&mut players, &mut steering_intents, &steerings).join() {
             let initiate_jump = jump_down && !player.pressing_jump;
             player.pressing_jump = jump_down;
@@ -0,0 +1,257 @@
+use crate::components::*;
+use crate::levels::EdgeRule;
+use crate::resources::*;
+use crate::systems::{aligned_with_grid, SoundEvent};
+use amethyst::core::ecs::shrev::EventChannel;
+use amethyst::core::{Time, Transform};
+use amethyst::ecs::prelude::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
+
+/// The speed in meters per second at which pushable blocks fall.
+const FALL_SPEED: f32 = 15.;
+
+/// Lets the player push blocks by walking into them while grounded.
+///
+/// A block is pushed one tile at a time, and only if every position it would move into is empty.
+/// If the block cannot move, the player is told so with a sound.
+#[derive(Default)]
+pub struct PushSystem {
+    /// The player's position and walking direction when a push last failed. Used to play the
+    /// failure sound only once, rather than every tick the player keeps walking into the block.
+    failed_push: Option<(Pos, Direction1D)>,
+}
+
+impl<'s> System<'s> for PushSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Write<'s, EventChannel<SoundEvent>>,
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, SteeringIntent>,
+        ReadStorage<'s, Steering>,
+        ReadStorage<'s, Pushable>,
+        ReadStorage<'s, Transform>,
+        WriteStorage<'s, Block>,
+        Write<'s, TileMap>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut sound_channel,
+            players,
+            intents,
+            steerings,
+            pushables,
+            transforms,
+            mut blocks,
+            mut tile_map,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let player = (&players, &intents, &steerings, &transforms)
+            .join()
+            .map(|(_, intent, steering, transform)| (intent.walk, steering, transform))
+            .next();
+        let (direction, steering, transform) = match player {
+            Some((direction, steering, transform))
+                if steering.is_grounded() && !direction.is_neutral() =>
+            {
+                (direction, steering, transform)
+            }
+            _ => {
+                self.failed_push = None;
+                return;
+            }
+        };
+        let (anchored_x, _) = steering.to_anchor_coords(transform);
+        if steering.destination != steering.pos
+            || !aligned_with_grid(steering.destination.x as f32, anchored_x, direction)
+        {
+            return;
+        }
+        let front_x = if direction.is_positive() {
+            steering.pos.x + steering.dimens.x
+        } else {
+            steering.pos.x - 1
+        };
+        let mut anchors = (0..steering.dimens.y)
+            .map(|y| Pos::new(front_x, steering.pos.y + y))
+            .filter(|pos| {
+                tile_map
+                    .get_tile(pos)
+                    .map(|tile| tile.collides_horizontally())
+                    .unwrap_or(false)
+            })
+            .filter_map(|pos| tile_map.get_anchor(&pos))
+            .collect::<Vec<Pos>>();
+        anchors.dedup();
+        let anchor = match anchors.as_slice() {
+            [anchor] => *anchor,
+            _ => return,
+        };
+        let block = (&pushables, &blocks, &transforms, &entities)
+            .join()
+            .find(|(_, block, _, _)| block.pos == anchor)
+            .map(|(pushable, _, transform, entity)| (*pushable, transform, entity));
+        let (pushable, block_transform, block_entity) = match block {
+            Some(block) => block,
+            None => return,
+        };
+        if !is_at_rest(&pushable, anchor, block_transform) {
+            return;
+        }
+        let destination = anchor.append_x(direction.signum_i());
+        if can_move_to(&pushable, anchor, destination, &tile_map, &steerings) {
+            tile_map.move_tiles(&[(anchor, destination)]);
+            if let Some(block) = blocks.get_mut(block_entity) {
+                block.pos = destination;
+            }
+            self.failed_push = None;
+        } else if self.failed_push != Some((steering.pos, direction)) {
+            sound_channel.single_write(SoundEvent::new(SoundType::CannotPerformAction));
+            self.failed_push = Some((steering.pos, direction));
+        }
+    }
+}
+
+/// Makes pushable blocks fall when there is nothing beneath them, and moves each block's
+/// Transform towards its discrete position.
+///
+/// A block falls until it lands on a tile that provides a platform or on an entity with Steering.
+/// Tiles that do not provide a platform, such as keys, pickups and triggers, are covered by the
+/// block until it moves off them again. What happens at the bottom of the level depends on the
+/// level's edge rule, like it does for other entities: the block wraps around to the top, unless
+/// the border acts as a wall.
+pub struct PushableSystem;
+
+impl<'s> System<'s> for PushableSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'s, Pushable>,
+        ReadStorage<'s, Steering>,
+        WriteStorage<'s, Block>,
+        WriteStorage<'s, Transform>,
+        Write<'s, TileMap>,
+        Read<'s, MovementConfig>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (pushables, steerings, mut blocks, mut transforms, mut tile_map, config, time): Self::SystemData,
+    ) {
+        for (pushable, block, transform) in (&pushables, &mut blocks, &mut transforms).join() {
+            if is_at_rest(pushable, block.pos, transform) {
+                let mut below = block.pos.append_y(-1);
+                let mut offset_y = 0;
+                if below.y < tile_map.pos.y && tile_map.wrapping.y != EdgeRule::Wall {
+                    offset_y = tile_map.dimens.y;
+                    below.y += offset_y;
+                }
+                if can_fall_to(pushable, block.pos, below, &tile_map, &steerings) {
+                    tile_map.move_tiles(&[(block.pos, below)]);
+                    block.pos = below;
+                    transform.set_translation_y(transform.translation().y + offset_y as f32);
+                }
+            }
+            let (target_x, target_y) = pushable.to_centered_coords(block.pos);
+            let x = approach(
+                transform.translation().x,
+                target_x,
+                config.player_speed * time.fixed_seconds(),
+            );
+            let y = approach(
+                transform.translation().y,
+                target_y,
+                FALL_SPEED * time.fixed_seconds(),
+            );
+            transform.set_translation_x(x);
+            transform.set_translation_y(y);
+        }
+    }
+}
+
+/// Moves the current value towards the target value, but by no more than max_step.
+fn approach(current: f32, target: f32, max_step: f32) -> f32 {
+    if (target - current).abs() <= max_step {
+        target
+    } else {
+        current + max_step * (target - current).signum()
+    }
+}
+
+/// True iff the block's Transform has caught up with its discrete position.
+fn is_at_rest(pushable: &Pushable, pos: Pos, transform: &Transform) -> bool {
+    let (target_x, target_y) = pushable.to_centered_coords(pos);
+    (transform.translation().x - target_x).abs() < f32::EPSILON
+        && (transform.translation().y - target_y).abs() < f32::EPSILON
+}
+
+/// True iff the block anchored at `from` can be pushed to `to`. That is the case if each position
+/// it would occupy is either empty or already part of the block, does not lie beyond a border that
+/// acts as a wall, and no entity with Steering stands there.
+///
+/// Blocks are never pushed into other tiles, even those that do not collide.
+fn can_move_to(
+    pushable: &Pushable,
+    from: Pos,
+    to: Pos,
+    tile_map: &TileMap,
+    steerings: &ReadStorage<'_, Steering>,
+) -> bool {
+    let positions = occupied_positions(pushable, to);
+    let tiles_are_free = positions.iter().all(|pos| {
+        !tile_map.is_boundary(pos)
+            && tile_map
+                .get_anchor(pos)
+                .map(|anchor| anchor == from)
+                .unwrap_or(true)
+    });
+    tiles_are_free && !is_occupied_by_steering(&positions, steerings)
+}
+
+/// True iff the block anchored at `from` can fall to `to`. That is the case if no position it
+/// would occupy holds a tile that provides a platform, other than the block itself, or lies beyond
+/// a border that acts as a wall, and no entity with Steering stands there.
+fn can_fall_to(
+    pushable: &Pushable,
+    from: Pos,
+    to: Pos,
+    tile_map: &TileMap,
+    steerings: &ReadStorage<'_, Steering>,
+) -> bool {
+    let positions = occupied_positions(pushable, to);
+    let nothing_to_land_on = positions.iter().all(|pos| {
+        !tile_map.is_boundary(pos)
+            && (tile_map.get_anchor(pos) == Some(from)
+                || !tile_map
+                    .get_tile(pos)
+                    .map(|tile| tile.provides_platform())
+                    .unwrap_or(false))
+    });
+    nothing_to_land_on && !is_occupied_by_steering(&positions, steerings)
+}
+
+/// All positions a block would take up if it were anchored at the given position.
+fn occupied_positions(pushable: &Pushable, anchor: Pos) -> Vec<Pos> {
+    (0..pushable.dimens.x)
+        .flat_map(|x| (0..pushable.dimens.y).map(move |y| anchor.append_xy(x, y)))
+        .collect()
+}
+
+/// True iff any entity with Steering stands at, or is on its way to, any of the given positions.
+fn is_occupied_by_steering(positions: &[Pos], steerings: &ReadStorage<'_, Steering>) -> bool {
+    steerings.join().any(|steering| {
+        positions.iter().any(|pos| {
+            occupies(steering, steering.pos, pos) || occupies(steering, steering.destination, pos)
+        })
+    })
+}
+
+/// True iff an entity with the given steering, standing at the given position, occupies `pos`.
+fn occupies(steering: &Steering, at: Pos, pos: &Pos) -> bool {
+    pos.x >= at.x
+        && pos.x < at.x + steering.dimens.x
+        && pos.y >= at.y
+        && pos.y < at.y + steering.dimens.y
+}
@@ -15,8 +15,8 @@ impl<'s> System<'s> for SteeringSystem {
         WriteStorage<'s, SteeringIntent>,
         ReadStorage<'s, Transform>,
         WriteStorage<'s, Steering>,
+        ReadStorage<'s, Mob>,
         Read<'s, TileMap>,
-        Write<'s, History>,
         Read<'s, Time>,
     );
 
@@ -27,17 +27,28 @@ impl<'s> System<'s> for SteeringSystem {
             mut steering_intents,
             transforms,
             mut steerings,
+            mobs,
             tile_map,
-            mut history,
             time,
         ): Self::SystemData,
     ) {
-        for (intent, transform, steering) in
-            (&mut steering_intents, &transforms, &mut steerings).join()
+        for (intent, transform, steering, mob) in (
+            &mut steering_intents,
+            &transforms,
+            &mut steerings,
+            mobs.maybe(),
+        )
+            .join()
         {
-            let old_pos = steering.pos;
+            // Mobs move around silently, only the player's movement makes sounds.
+            let mut play_sound = |sound_type| {
+                if mob.is_none() {
+                    sound_channel.single_write(SoundEvent::new(sound_type));
+                }
+            };
             let (anchored_x, anchored_y) = steering.to_anchor_coords(transform);
             steering.pos = Pos::new(anchored_x.round() as i32, anchored_y.round() as i32);
+            steering.landed_after_fall = None;
 
             if steering.is_mid_air() {
                 steering.mode = steering.mode.add_to_duration(time.fixed_seconds());
@@ -55,6 +66,12 @@ impl<'s> System<'s> for SteeringSystem {
                 && on_solid_ground(steering, &tile_map)
             {
                 // If falling and you reached the floor, set to grounded.
+                if let SteeringMode::Falling { starting_y_pos, .. } = steering.mode {
+                    // The fall started at a centered y coordinate, so compare it to the centered
+                    // coordinates of the landing position.
+                    let (_, landed_y) = steering.to_centered_coords(steering.pos);
+                    steering.landed_after_fall = Some(starting_y_pos - landed_y);
+                }
                 steering.mode = SteeringMode::Grounded;
                 steering.destination = steering.pos;
             } else if (steering.is_grounded()
@@ -69,9 +86,9 @@ impl<'s> System<'s> for SteeringSystem {
                 };
             } else if steering.is_grounded() && intent.jump {
                 if is_underneath_ceiling(steering, &tile_map) {
-                    sound_channel.single_write(SoundEvent::new(SoundType::CannotPerformAction));
+                    play_sound(SoundType::CannotPerformAction);
                 } else {
-                    sound_channel.single_write(SoundEvent::new(SoundType::Jump));
+                    play_sound(SoundType::Jump);
                     steering.mode = SteeringMode::Jumping {
                         x_movement: intent.face,
                         starting_y_pos: transform.translation().y,
@@ -109,14 +126,14 @@ impl<'s> System<'s> for SteeringSystem {
                         if offset_from_destination < f32::EPSILON && intent.walk.is_positive() {
                             if !is_against_wall_right(&steering, steering.pos.y as f32, &tile_map) {
                                 steering.destination.x = steering.pos.x + 1;
-                                sound_channel.single_write(SoundEvent::new(SoundType::Step));
+                                play_sound(SoundType::Step);
                             }
                         } else if offset_from_destination > -f32::EPSILON
                             && intent.walk.is_negative()
                         {
                             if !is_against_wall_left(&steering, steering.pos.y as f32, &tile_map) {
                                 steering.destination.x = steering.pos.x - 1;
-                                sound_channel.single_write(SoundEvent::new(SoundType::Step));
+                                play_sound(SoundType::Step);
                             }
                         } else if !intent
                             .walk
@@ -134,7 +151,7 @@ impl<'s> System<'s> for SteeringSystem {
                         let offset_from_discrete_pos = steering.destination.y as f32 - anchored_y;
                         if offset_from_discrete_pos < f32::EPSILON && intent.climb.is_positive() {
                             if can_climb_up(steering, &tile_map) {
-                                sound_channel.single_write(SoundEvent::new(SoundType::LadderStep));
+                                play_sound(SoundType::LadderStep);
                                 steering.destination.y = steering.pos.y + 1;
                             } else {
                                 steering.mode = SteeringMode::Grounded;
@@ -143,7 +160,7 @@ impl<'s> System<'s> for SteeringSystem {
                             && intent.climb.is_negative()
                         {
                             if can_climb_down(steering, &tile_map) {
-                                sound_channel.single_write(SoundEvent::new(SoundType::LadderStep));
+                                play_sound(SoundType::LadderStep);
                                 steering.destination.y = steering.pos.y - 1;
                             } else if above_air(steering, &tile_map) {
                                 steering.mode = SteeringMode::Falling {
@@ -238,18 +255,13 @@ impl<'s> System<'s> for SteeringSystem {
                     }
                 }
             };
-
-            // Push frame on history if player position changed.
-            if old_pos != steering.pos || history.force_key_frame {
-                history.push_frame(Frame::new(steering.pos));
-            }
         }
     }
 }
 
 /// Returns true iff the player is aligned with the grid.
 /// This function can be used for both horizontal and vertical coordinates.
-fn aligned_with_grid(destination_pos: f32, actual_pos: f32, input: Direction1D) -> bool {
+pub fn aligned_with_grid(destination_pos: f32, actual_pos: f32, input: Direction1D) -> bool {
     let offset = actual_pos - destination_pos;
     // Actual pos equal or greater than destination. Moving towards the positive.
     (offset > -f32::EPSILON && input.is_positive())
@@ -300,11 +312,13 @@ fn is_underneath_ceiling(steering: &Steering, tile_map: &TileMap) -> bool {
     })
 }
 
-fn is_against_wall_left(steering: &Steering, anchored_y: f32, tile_map: &TileMap) -> bool {
+/// Returns true iff a wall keeps the entity from moving to the left, at the given height.
+pub fn is_against_wall_left(steering: &Steering, anchored_y: f32, tile_map: &TileMap) -> bool {
     is_against_wall(&steering, anchored_y, &tile_map, -1, 0)
 }
 
-fn is_against_wall_right(steering: &Steering, anchored_y: f32, tile_map: &TileMap) -> bool {
+/// Returns true iff a wall keeps the entity from moving to the right, at the given height.
+pub fn is_against_wall_right(steering: &Steering, anchored_y: f32, tile_map: &TileMap) -> bool {
     is_against_wall(
         &steering,
         anchored_y,
@@ -343,11 +357,13 @@ fn is_against_wall(
     })
 }
 
-fn can_climb_up(steering: &Steering, tile_map: &TileMap) -> bool {
+/// Returns true iff the entity stands on a ladder and can climb up one tile.
+pub fn can_climb_up(steering: &Steering, tile_map: &TileMap) -> bool {
     can_climb(steering, tile_map, (0, 1)) && !is_underneath_ceiling(steering, &tile_map)
 }
 
-fn can_climb_down(steering: &Steering, tile_map: &TileMap) -> bool {
+/// Returns true iff the entity stands on top of or on a ladder and can climb down one tile.
+pub fn can_climb_down(steering: &Steering, tile_map: &TileMap) -> bool {
     can_climb(steering, tile_map, (-1, 0))
 }
 
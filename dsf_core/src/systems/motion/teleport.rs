@@ -0,0 +1,75 @@
+use crate::components::{Player, Steering, Teleporter};
+use crate::resources::{SoundType, TileMap, WinCondition};
+use crate::systems::{reposition, SoundEvent};
+use amethyst::core::ecs::shrev::EventChannel;
+use amethyst::core::ecs::{Join, Read, ReadStorage, System, Write, WriteStorage};
+use amethyst::core::timing::Time;
+use amethyst::core::Transform;
+
+/// How many seconds must pass after teleporting before the player can teleport again.
+const TELEPORT_COOLDOWN: f32 = 0.5;
+
+/// Sends the player to the partner of the teleporter they step onto.
+///
+/// The player keeps their position relative to the teleporter, as well as their steering mode:
+/// if they fall into a teleporter, they come out of its partner still falling.
+pub struct TeleportSystem;
+
+impl<'s> System<'s> for TeleportSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Write<'s, EventChannel<SoundEvent>>,
+        WriteStorage<'s, Player>,
+        WriteStorage<'s, Steering>,
+        WriteStorage<'s, Transform>,
+        ReadStorage<'s, Teleporter>,
+        Read<'s, TileMap>,
+        Read<'s, WinCondition>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut sound_channel,
+            mut players,
+            mut steerings,
+            mut transforms,
+            teleporters,
+            tile_map,
+            win,
+            time,
+        ): Self::SystemData,
+    ) {
+        if win.is_dying() {
+            return;
+        }
+        for (player, steering, transform) in (&mut players, &mut steerings, &mut transforms).join()
+        {
+            let entered = teleporters
+                .join()
+                .find(|teleporter| teleporter.is_overlapped_by(steering));
+            if let Some(cooldown) = player.teleport_cooldown {
+                let cooldown = cooldown - time.fixed_seconds();
+                player.teleport_cooldown = if cooldown <= 0. && entered.is_none() {
+                    None
+                } else {
+                    Some(cooldown.max(0.))
+                };
+                continue;
+            }
+            let partner = entered.and_then(|teleporter| {
+                tile_map
+                    .get_properties(&teleporter.pos)
+                    .and_then(|properties| properties.pos("partner"))
+                    .map(|partner| (teleporter.pos, partner))
+            });
+            if let Some((pos, partner)) = partner {
+                let offset = partner.append_xy(-pos.x, -pos.y);
+                reposition(steering, transform, &offset);
+                player.teleport_cooldown = Some(TELEPORT_COOLDOWN);
+                sound_channel.single_write(SoundEvent::new(SoundType::Teleport));
+            }
+        }
+    }
+}
@@ -1,11 +1,134 @@
 use crate::components::*;
+use crate::levels::*;
 use crate::resources::*;
+use crate::systems::{apply_triggers, build_dynamite, build_equipped_tool, SoundEvent};
+use amethyst::core::ecs::shrev::EventChannel;
+use amethyst::core::ecs::{Entities, LazyUpdate};
 use amethyst::{
+    core::math::Vector3,
     core::timing::Time,
     core::transform::Transform,
-    ecs::prelude::{Join, Read, ReadStorage, System, Write, WriteStorage},
+    ecs::prelude::{Entity, Join, Read, ReadStorage, System, World, Write, WriteStorage},
     input::{InputHandler, StringBindings},
+    prelude::WorldExt,
 };
+use std::collections::BTreeSet;
+
+/// Records a Frame in the History whenever something in the game changed. Also records a
+/// checkpoint whenever the player starts an action or lands, so the action can be undone.
+///
+/// Runs at the start of each tick, before any of the game play systems. That way, the recorded
+/// state is always complete: all lazy updates of the previous tick have been applied.
+#[derive(Default)]
+pub struct HistorySystem {
+    /// The state of the game at the start of the previous tick.
+    previous_tick: Option<Frame>,
+}
+
+impl<'s> System<'s> for HistorySystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, Steering>,
+        ReadStorage<'s, Transform>,
+        ReadStorage<'s, Tool>,
+        ReadStorage<'s, Block>,
+        ReadStorage<'s, Mob>,
+        ReadStorage<'s, Pushable>,
+        ReadStorage<'s, Dynamite>,
+        Read<'s, WinCondition>,
+        Read<'s, TileMap>,
+        Read<'s, Triggers>,
+        Read<'s, KeyRing>,
+        Write<'s, History>,
+        Read<'s, Time>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            players,
+            steerings,
+            transforms,
+            tools,
+            blocks,
+            mobs,
+            pushables,
+            dynamites,
+            win,
+            tile_map,
+            triggers,
+            key_ring,
+            mut history,
+            time,
+            entities,
+        ): Self::SystemData,
+    ) {
+        history.tick(time.fixed_seconds());
+        let player = (&players, &steerings, &transforms).join().next();
+        let frame = Frame {
+            player_steering: player.map(|(_, steering, _)| steering.clone()),
+            player_translation: player
+                .map(|(_, _, transform)| (transform.translation().x, transform.translation().y))
+                .unwrap_or_default(),
+            equipped: player.and_then(|(player, _, _)| player.equipped),
+            keys: win.keys.iter().copied().collect(),
+            held_keys: key_ring.held.clone(),
+            tools: (&tools, &blocks, &entities)
+                .join()
+                .map(|(_, block, _)| block.pos)
+                .collect(),
+            removed_tiles: tile_map.removed_tiles().copied().collect(),
+            placed_tiles: tile_map.placed_tiles().clone(),
+            fired_triggers: triggers.fired.clone(),
+            pressed_triggers: triggers.pressed.clone(),
+            pushables: (&pushables, &blocks)
+                .join()
+                .map(|(pushable, block)| (pushable.origin, block.pos))
+                .collect(),
+            mobs: (&mobs, &steerings, &transforms, &blocks)
+                .join()
+                .map(|(mob, steering, transform, block)| MobFrame {
+                    spawn: block.pos,
+                    mob: *mob,
+                    steering: steering.clone(),
+                    translation: (transform.translation().x, transform.translation().y),
+                })
+                .collect(),
+            dynamite: {
+                let mut dynamite = dynamites.join().copied().collect::<Vec<Dynamite>>();
+                dynamite.sort_by_key(|dynamite| dynamite.pos);
+                dynamite
+            },
+        };
+        // After rewinding or undoing, the previous tick no longer precedes the current state.
+        let previous_tick = self
+            .previous_tick
+            .take()
+            .filter(|_| !history.force_key_frame);
+        let lands = previous_tick
+            .as_ref()
+            .map(|previous_tick| frame.lands_since(previous_tick))
+            .unwrap_or(false);
+        if let Some(previous_tick) = previous_tick {
+            if frame.starts_action_since(&previous_tick) {
+                history.push_checkpoint(previous_tick);
+            }
+        }
+        let changed = history
+            .last_frame()
+            .map(|last_frame| frame.has_changed_since(last_frame))
+            .unwrap_or(true);
+        if changed || history.force_key_frame {
+            history.push_frame(frame.clone());
+        }
+        if lands {
+            history.push_checkpoint(frame.clone());
+        }
+        self.previous_tick = Some(frame);
+    }
+}
 
 pub struct RewindControlSystem;
 
@@ -41,41 +164,395 @@ impl<'s> System<'s> for RewindControlSystem {
             if CurrentState::Rewinding == *current_state {
                 history.force_key_frame = true;
             }
+            rewind.reported_oldest_frame = false;
             *current_state = CurrentState::Running;
         }
     }
 }
 
+/// While rewinding, pops Frames off the History and puts the game back in the recorded state.
+///
+/// Once there are no Frames left, the player is told so, once: either they are back at the start
+/// of the level, or they tried to rewind further than the History reaches.
 pub struct RewindSystem;
 
 impl<'s> System<'s> for RewindSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = (
-        WriteStorage<'s, Transform>,
-        WriteStorage<'s, Steering>,
-        ReadStorage<'s, Player>,
-        Read<'s, Rewind>,
+        Write<'s, EventChannel<SoundEvent>>,
+        Write<'s, Rewind>,
         Write<'s, History>,
+        Write<'s, ReplayRecorder>,
+        Read<'s, LazyUpdate>,
     );
 
     fn run(
         &mut self,
-        (mut transforms, mut steerings, player_tags, rewind, mut history): Self::SystemData,
+        (mut sound_channel, mut rewind, mut history, mut recorder, lazy): Self::SystemData,
     ) {
-        if rewind.is_ready() {
-            if let Some(frame) = history.pop_frame() {
-                info!("Rewinding player to {:?}", frame);
-                for (_, transform, steering) in
-                    (&player_tags, &mut transforms, &mut steerings).join()
-                {
-                    let (centered_x, centered_y) =
-                        steering.to_centered_coords(frame.player_position);
+        if !rewind.is_ready() {
+            return;
+        }
+        if let Some(frame) = history.pop_frame() {
+            info!("Rewinding to {:?}", frame);
+            recorder.interrupt();
+            lazy.exec_mut(move |world| restore_frame(world, &frame));
+        } else if !rewind.reported_oldest_frame {
+            rewind.reported_oldest_frame = true;
+            if history.is_truncated() {
+                info!(
+                    "Cannot rewind any further, only the last {} seconds are kept.",
+                    history.max_rewind_seconds()
+                );
+            } else {
+                info!("Rewound all the way back to the start of the level.");
+            }
+            sound_channel.single_write(SoundEvent::new(SoundType::CannotPerformAction));
+        }
+    }
+}
+
+/// Puts the game back in the state recorded in the given Frame.
+///
+/// This both updates the resources and re-creates or deletes entities as needed: keys, tools and
+/// blocks that were removed since the Frame was recorded are built again.
+pub fn restore_frame(world: &mut World, frame: &Frame) {
+    revive_player(world);
+    restore_player(world, frame);
+    restore_mobs(world, frame);
+    restore_pushables(world, frame);
+    restore_placed_tiles(world, frame);
+    restore_triggers(world, frame);
+    restore_removed_tiles(world, frame);
+    restore_keys(world, frame);
+    world.write_resource::<KeyRing>().held = frame.held_keys.clone();
+    restore_tools(world, frame);
+    restore_dynamite(world, frame);
+}
+
+/// Brings the player back to life if they are dying: undoes the death animation.
+/// A player who lost the level stays dead; only restarting the level brings them back.
+fn revive_player(world: &mut World) {
+    if world.read_resource::<WinCondition>().lost() {
+        return;
+    }
+    world.exec(
+        |(entities, mut dyings, mut transforms, mut win): (
+            Entities,
+            WriteStorage<Dying>,
+            WriteStorage<Transform>,
+            Write<WinCondition>,
+        )| {
+            win.killed_by = None;
+            let revived = (&entities, &dyings, &mut transforms)
+                .join()
+                .map(|(entity, dying, transform)| {
+                    transform.set_scale(Vector3::new(dying.scale.0, dying.scale.1, dying.scale.2));
+                    transform.set_rotation_2d(0.);
+                    entity
+                })
+                .collect::<Vec<Entity>>();
+            revived.iter().for_each(|entity| {
+                dyings.remove(*entity);
+            });
+        },
+    );
+}
+
+fn restore_player(world: &mut World, frame: &Frame) {
+    let restored = world.exec(
+        |(entities, mut players, mut steerings, mut transforms): (
+            Entities,
+            WriteStorage<Player>,
+            WriteStorage<Steering>,
+            WriteStorage<Transform>,
+        )| {
+            let mut restored = None;
+            for (entity, player, steering, transform) in
+                (&entities, &mut players, &mut steerings, &mut transforms).join()
+            {
+                if let Some(recorded) = &frame.player_steering {
+                    *steering = recorded.clone();
+                }
+                transform.set_translation_x(frame.player_translation.0);
+                transform.set_translation_y(frame.player_translation.1);
+                restored = Some((entity, player.equipped));
+                player.equipped = frame.equipped;
+                // Don't let a restored player be teleported away before they step off the
+                // teleporter they might be standing on.
+                player.teleport_cooldown = Some(0.);
+            }
+            restored
+        },
+    );
+    if let Some((player_entity, equipped_before)) = restored {
+        if equipped_before != frame.equipped {
+            rebuild_equipped_tool(world, player_entity, frame.equipped);
+        }
+    }
+}
+
+fn restore_mobs(world: &mut World, frame: &Frame) {
+    world.exec(
+        |(blocks, mut mobs, mut steerings, mut transforms): (
+            ReadStorage<Block>,
+            WriteStorage<Mob>,
+            WriteStorage<Steering>,
+            WriteStorage<Transform>,
+        )| {
+            for (block, mob, steering, transform) in
+                (&blocks, &mut mobs, &mut steerings, &mut transforms).join()
+            {
+                let mob_frame = frame
+                    .mobs
+                    .iter()
+                    .find(|mob_frame| mob_frame.spawn == block.pos);
+                if let Some(mob_frame) = mob_frame {
+                    *mob = mob_frame.mob;
+                    *steering = mob_frame.steering.clone();
+                    transform.set_translation_x(mob_frame.translation.0);
+                    transform.set_translation_y(mob_frame.translation.1);
+                }
+            }
+        },
+    );
+}
+
+fn restore_pushables(world: &mut World, frame: &Frame) {
+    world.exec(
+        |(pushables, mut blocks, mut transforms, mut tile_map): (
+            ReadStorage<Pushable>,
+            WriteStorage<Block>,
+            WriteStorage<Transform>,
+            Write<TileMap>,
+        )| {
+            let mut moves = vec![];
+            for (pushable, block, transform) in (&pushables, &mut blocks, &mut transforms).join() {
+                let recorded = frame
+                    .pushables
+                    .iter()
+                    .find(|(origin, _)| *origin == pushable.origin)
+                    .map(|(_, pos)| *pos);
+                if let Some(recorded) = recorded {
+                    if recorded != block.pos {
+                        moves.push((block.pos, recorded));
+                        block.pos = recorded;
+                    }
+                    let (centered_x, centered_y) = pushable.to_centered_coords(recorded);
                     transform.set_translation_x(centered_x);
                     transform.set_translation_y(centered_y);
-                    steering.pos = frame.player_position;
-                    steering.destination = frame.player_position;
                 }
             }
+            tile_map.move_tiles(&moves);
+        },
+    );
+}
+
+/// Replaces the sprite of the equipped tool with one for the given tool type.
+pub fn rebuild_equipped_tool(world: &mut World, player_entity: Entity, equipped: Option<ToolType>) {
+    let equipped_entities = world.exec(
+        |(entities, equipped_tags): (Entities, ReadStorage<EquippedTag>)| {
+            (&entities, &equipped_tags)
+                .join()
+                .map(|(entity, _)| entity)
+                .collect::<Vec<Entity>>()
+        },
+    );
+    if let Err(err) = world.delete_entities(&equipped_entities) {
+        error!("Failed to delete equipped tool sprite: {:?}", err);
+    }
+    if let Some(tool_type) = equipped {
+        let sprite = world
+            .read_resource::<TileMap>()
+            .tile_defs()
+            .tool_sprite(tool_type);
+        if let Some((sprite, sprite_nr)) = sprite {
+            build_equipped_tool(world, player_entity, sprite, sprite_nr);
+        } else {
+            error!("No tool definition with a still asset for {:?}.", tool_type);
         }
     }
 }
+
+fn restore_placed_tiles(world: &mut World, frame: &Frame) {
+    let placed = world.read_resource::<TileMap>().placed_tiles().clone();
+    let gone = placed
+        .keys()
+        .filter(|anchor| !frame.placed_tiles.contains_key(anchor))
+        .copied()
+        .collect::<BTreeSet<Pos>>();
+    {
+        let mut tile_map = world.write_resource::<TileMap>();
+        gone.iter().for_each(|anchor| tile_map.unplace_tile(anchor));
+    }
+    // The player and mobs also have a Block, at the position they spawned at. Leave them be.
+    delete_entities_at(world, &gone, |world, entity| {
+        world.read_storage::<Steering>().get(entity).is_none()
+    });
+    frame
+        .placed_tiles
+        .iter()
+        .filter(|(anchor, _)| !placed.contains_key(anchor))
+        .for_each(|(anchor, key)| {
+            world.write_resource::<TileMap>().place_tile(anchor, key);
+            rebuild_tile(world, anchor, key);
+        });
+}
+
+fn restore_triggers(world: &mut World, frame: &Frame) {
+    {
+        let mut triggers = world.write_resource::<Triggers>();
+        triggers.fired = frame.fired_triggers.clone();
+        triggers.pressed = frame.pressed_triggers.clone();
+    }
+    apply_triggers(world);
+}
+
+fn restore_removed_tiles(world: &mut World, frame: &Frame) {
+    let (to_restore, to_remove) = {
+        let tile_map = world.read_resource::<TileMap>();
+        let to_restore = tile_map
+            .removed_tiles()
+            .filter(|pos| !frame.removed_tiles.contains(pos))
+            .copied()
+            .collect::<Vec<Pos>>();
+        let to_remove = frame
+            .removed_tiles
+            .iter()
+            .filter(|pos| tile_map.get_tile(pos).is_some())
+            .copied()
+            .collect::<BTreeSet<Pos>>();
+        (to_restore, to_remove)
+    };
+    for pos in to_restore {
+        let key = world.write_resource::<TileMap>().restore_tile(&pos);
+        if let Some(key) = key {
+            rebuild_tile(world, &pos, &key);
+        }
+    }
+    if !to_remove.is_empty() {
+        let mut tile_map = world.write_resource::<TileMap>();
+        to_remove.iter().for_each(|pos| tile_map.remove_tile(pos));
+    }
+    delete_entities_at(world, &to_remove, |world, entity| {
+        world.read_storage::<Player>().get(entity).is_none()
+    });
+}
+
+fn restore_keys(world: &mut World, frame: &Frame) {
+    let present = world.exec(|(entities, keys): (Entities, ReadStorage<Key>)| {
+        (&entities, &keys)
+            .join()
+            .map(|(_, key)| key.pos)
+            .collect::<BTreeSet<Pos>>()
+    });
+    world.write_resource::<WinCondition>().keys = frame.keys.iter().copied().collect();
+    if present == frame.keys {
+        return;
+    }
+    let gone = present.difference(&frame.keys).copied().collect();
+    delete_entities_at(world, &gone, |world, entity| {
+        world.read_storage::<Key>().get(entity).is_some()
+    });
+    frame
+        .keys
+        .difference(&present)
+        .for_each(|pos| rebuild_tile_at(world, pos));
+    if world.has_value::<Assets>() {
+        let displays = world.exec(
+            |(entities, key_displays): (Entities, ReadStorage<KeyDisplay>)| {
+                (&entities, &key_displays)
+                    .join()
+                    .map(|(entity, _)| entity)
+                    .collect::<Vec<Entity>>()
+            },
+        );
+        if let Err(err) = world.delete_entities(&displays) {
+            error!("Failed to delete key displays: {:?}", err);
+        }
+        add_key_displays_to_door(world);
+    }
+}
+
+fn restore_tools(world: &mut World, frame: &Frame) {
+    let present = world.exec(
+        |(entities, tools, blocks): (Entities, ReadStorage<Tool>, ReadStorage<Block>)| {
+            (&entities, &tools, &blocks)
+                .join()
+                .map(|(_, _, block)| block.pos)
+                .collect::<BTreeSet<Pos>>()
+        },
+    );
+    let gone = present.difference(&frame.tools).copied().collect();
+    delete_entities_at(world, &gone, |world, entity| {
+        world.read_storage::<Tool>().get(entity).is_some()
+    });
+    frame
+        .tools
+        .difference(&present)
+        .for_each(|pos| rebuild_tile_at(world, pos));
+}
+
+/// Replaces all placed dynamite with the dynamite in the Frame, fuses included.
+fn restore_dynamite(world: &mut World, frame: &Frame) {
+    let placed = world.exec(|(entities, dynamites): (Entities, ReadStorage<Dynamite>)| {
+        (&entities, &dynamites)
+            .join()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<Entity>>()
+    });
+    if let Err(err) = world.delete_entities(&placed) {
+        error!("Failed to delete dynamite while rewinding: {:?}", err);
+    }
+    frame
+        .dynamite
+        .iter()
+        .for_each(|dynamite| build_dynamite(world, *dynamite));
+}
+
+/// Builds the entity for the tile that the level has at the given position.
+fn rebuild_tile_at(world: &mut World, pos: &Pos) {
+    let key = world
+        .read_resource::<TileMap>()
+        .get_tile_def_key(pos)
+        .cloned();
+    match key {
+        Some(key) => rebuild_tile(world, pos, &key),
+        None => error!("Cannot rebuild tile at {:?}, there is no tile there.", pos),
+    }
+}
+
+fn rebuild_tile(world: &mut World, pos: &Pos, key: &str) {
+    let tile_def = world
+        .read_resource::<TileMap>()
+        .tile_defs()
+        .get(key)
+        .clone();
+    build_tile(world, pos, key, &tile_def);
+}
+
+/// Deletes all entities with a Block at one of the given positions, for which the filter returns
+/// true.
+pub fn delete_entities_at(
+    world: &mut World,
+    positions: &BTreeSet<Pos>,
+    filter: impl Fn(&World, Entity) -> bool,
+) {
+    if positions.is_empty() {
+        return;
+    }
+    let candidates = world.exec(|(entities, blocks): (Entities, ReadStorage<Block>)| {
+        (&entities, &blocks)
+            .join()
+            .filter(|(_, block)| positions.contains(&block.pos))
+            .map(|(entity, _)| entity)
+            .collect::<Vec<Entity>>()
+    });
+    let doomed = candidates
+        .into_iter()
+        .filter(|entity| filter(world, *entity))
+        .collect::<Vec<Entity>>();
+    if let Err(err) = world.delete_entities(&doomed) {
+        error!("Failed to delete entities: {:?}", err);
+    }
+}
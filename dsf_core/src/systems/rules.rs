@@ -0,0 +1,116 @@
+use crate::resources::CurrentState;
+use crate::systems::*;
+use amethyst::core::SystemExt;
+use amethyst::ecs::DispatcherBuilder;
+
+/// Adds the systems that make up the rules of the game to the dispatcher: movement, collecting
+/// keys and tools, using tools, wrapping, teleporting, winning and dying.
+///
+/// Both the PlayState and the headless Simulation build their dispatchers with this. Every system
+/// depends on the one before it, so the rules run in the same order each tick, whether the
+/// dispatcher runs its systems in parallel or one after another. That is what lets the
+/// Simulation replay a run recorded in the game faithfully. The first system depends on the given
+/// dependencies; the last one is the "death_system".
+///
+/// The rules only run while the game is running, not while it is rewinding.
+pub fn add_game_rules<'a, 'b>(
+    builder: DispatcherBuilder<'a, 'b>,
+    dependencies: &[&str],
+) -> DispatcherBuilder<'a, 'b> {
+    builder
+        .with(
+            PlayerSystem.pausable(CurrentState::Running),
+            "player_system",
+            dependencies,
+        )
+        .with(
+            MobSystem.pausable(CurrentState::Running),
+            "mob_system",
+            &["player_system"],
+        )
+        .with(
+            SteeringSystem.pausable(CurrentState::Running),
+            "steering_system",
+            &["mob_system"],
+        )
+        .with(
+            PushSystem::default().pausable(CurrentState::Running),
+            "push_system",
+            &["steering_system"],
+        )
+        .with(
+            PushableSystem.pausable(CurrentState::Running),
+            "pushable_system",
+            &["push_system"],
+        )
+        .with(
+            MovementSystem.pausable(CurrentState::Running),
+            "movement_system",
+            &["pushable_system"],
+        )
+        .with(
+            VelocitySystem.pausable(CurrentState::Running),
+            "velocity_system",
+            &["movement_system"],
+        )
+        .with(
+            KeyCollectionSystem.pausable(CurrentState::Running),
+            "key_collection_system",
+            &["velocity_system"],
+        )
+        .with(
+            ColouredKeySystem.pausable(CurrentState::Running),
+            "coloured_key_system",
+            &["key_collection_system"],
+        )
+        .with(
+            PickupSystem.pausable(CurrentState::Running),
+            "pickup_system",
+            &["coloured_key_system"],
+        )
+        .with(
+            UseToolSystem::default().pausable(CurrentState::Running),
+            "use_tool_system",
+            &["pickup_system"],
+        )
+        .with(
+            DynamiteSystem.pausable(CurrentState::Running),
+            "dynamite_system",
+            &["use_tool_system"],
+        )
+        .with(
+            LevelWrappingSystem.pausable(CurrentState::Running),
+            "level_wrapping_system",
+            &["dynamite_system"],
+        )
+        .with(
+            TeleportSystem.pausable(CurrentState::Running),
+            "teleport_system",
+            &["level_wrapping_system"],
+        )
+        .with(
+            WinSystem.pausable(CurrentState::Running),
+            "win_system",
+            &["teleport_system"],
+        )
+        .with(
+            MobContactSystem.pausable(CurrentState::Running),
+            "mob_contact_system",
+            &["win_system"],
+        )
+        .with(
+            TriggerSystem.pausable(CurrentState::Running),
+            "trigger_system",
+            &["mob_contact_system"],
+        )
+        .with(
+            HazardSystem.pausable(CurrentState::Running),
+            "hazard_system",
+            &["trigger_system"],
+        )
+        .with(
+            DeathSystem.pausable(CurrentState::Running),
+            "death_system",
+            &["hazard_system"],
+        )
+}
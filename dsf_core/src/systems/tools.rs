@@ -2,8 +2,7 @@ use amethyst::core::ecs::{Entities, LazyUpdate};
 use amethyst::{
     core::math::Vector2,
     core::transform::Transform,
-    ecs::prelude::{Join, Read, ReadStorage, System, Write, WriteStorage},
-    input::{InputHandler, StringBindings},
+    ecs::prelude::{Entity, Join, Read, ReadStorage, System, World, Write, WriteStorage},
 };
 
 use crate::components::*;
@@ -11,6 +10,7 @@ use crate::levels::*;
 use crate::resources::*;
 use crate::systems::SoundEvent;
 use amethyst::core::ecs::shrev::EventChannel;
+use amethyst::core::timing::Time;
 use amethyst::core::Parent;
 use amethyst::prelude::{Builder, WorldExt};
 
@@ -70,28 +70,49 @@ impl<'s> System<'s> for PickupSystem {
                 sound_channel.single_write(SoundEvent::new(SoundType::ToolPickup));
                 player.equipped = Some(tool.tool_type);
                 let (sprite, sprite_nr) = (tool.sprite, tool.sprite_nr);
+                entities
+                    .delete(tool_entity)
+                    .expect("Tried to delete tool, but failed.");
                 lazy.exec_mut(move |world| {
-                    world
-                        .delete_entity(tool_entity)
-                        .expect("Tried to delete tool, but failed.");
-                    let render = load_asset_from_world(&sprite, sprite_nr, world);
-                    world
-                        .create_entity()
-                        .with(EquippedTag)
-                        .with(Transform::default())
-                        .with(Parent {
-                            entity: player_entity,
-                        })
-                        .with(render)
-                        .build();
+                    build_equipped_tool(world, player_entity, sprite, sprite_nr);
                 });
             }
         }
     }
 }
 
+/// Creates the sprite of the tool the player has equipped, which follows the player around.
+pub fn build_equipped_tool(
+    world: &mut World,
+    player_entity: Entity,
+    sprite: SpriteType,
+    sprite_nr: usize,
+) {
+    // A headless simulation has no assets, the equipped tool is not drawn there.
+    let render = if world.has_value::<Assets>() {
+        Some(load_asset_from_world(&sprite, sprite_nr, world))
+    } else {
+        None
+    };
+    let mut builder = world
+        .create_entity()
+        .with(EquippedTag)
+        .with(Transform::default())
+        .with(Parent {
+            entity: player_entity,
+        });
+    if let Some(render) = render {
+        builder = builder.with(render);
+    }
+    builder.build();
+}
+
 #[derive(Default)]
-pub struct UseToolSystem;
+pub struct UseToolSystem {
+    /// The player's position and facing when placing a ladder last failed. Used to play the
+    /// failure sound only once, rather than every tick the player keeps holding the button.
+    failed_ladder: Option<(Pos, Direction1D)>,
+}
 
 impl<'s> System<'s> for UseToolSystem {
     #[allow(clippy::type_complexity)]
@@ -101,8 +122,9 @@ impl<'s> System<'s> for UseToolSystem {
         ReadStorage<'s, Steering>,
         ReadStorage<'s, EquippedTag>,
         ReadStorage<'s, Block>,
-        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, InputSnapshot>,
         Write<'s, TileMap>,
+        Read<'s, LazyUpdate>,
         Entities<'s>,
     );
 
@@ -116,17 +138,66 @@ impl<'s> System<'s> for UseToolSystem {
             blocks,
             input,
             mut tile_map,
+            lazy,
             entities,
         ): Self::SystemData,
     ) {
-        let wants_to_use_tool = input.action_is_down("jump").unwrap_or(false);
+        let wants_to_use_tool = input.jump;
         if !wants_to_use_tool {
+            self.failed_ladder = None;
             return;
         }
         for (player, steering) in (&mut players, &steerings).join() {
             if !steering.is_grounded() {
                 return;
             }
+            if let Some(ToolType::Dynamite { radius, fuse_ms }) = player.equipped {
+                player.equipped = None;
+                for (_, entity) in (&equipped_tags, &entities).join() {
+                    entities
+                        .delete(entity)
+                        .expect("Failed to delete equipped tool sprite.");
+                }
+                let dynamite = Dynamite::new(dynamite_placement(steering), radius, fuse_ms);
+                lazy.exec_mut(move |world| build_dynamite(world, dynamite));
+                return;
+            }
+            if let Some(ToolType::PlaceLadder(length)) = player.equipped {
+                let key = match tile_map.tile_defs().ladder_segment() {
+                    Some(key) => key.clone(),
+                    None => {
+                        error!("Cannot place ladder, there is no climbable block definition.");
+                        return;
+                    }
+                };
+                if let Some(anchors) = ladder_placement(steering, &tile_map, &key, length) {
+                    sound_channel.single_write(SoundEvent::new(SoundType::LadderStep));
+                    player.equipped = None;
+                    anchors
+                        .iter()
+                        .for_each(|anchor| tile_map.place_tile(anchor, &key));
+                    for (_, entity) in (&equipped_tags, &entities).join() {
+                        entities
+                            .delete(entity)
+                            .expect("Failed to delete equipped tool sprite.");
+                    }
+                    lazy.exec_mut(move |world| {
+                        let tile_def = world
+                            .read_resource::<TileMap>()
+                            .tile_defs()
+                            .get(&key)
+                            .clone();
+                        anchors
+                            .iter()
+                            .for_each(|anchor| build_tile(world, anchor, &key, &tile_def));
+                    });
+                    self.failed_ladder = None;
+                } else if self.failed_ladder != Some((steering.pos, steering.facing.x)) {
+                    sound_channel.single_write(SoundEvent::new(SoundType::CannotPerformAction));
+                    self.failed_ladder = Some((steering.pos, steering.facing.x));
+                }
+                return;
+            }
             let targeted_blocks = match player.equipped {
                 Some(ToolType::BreakBlocksHorizontally(depth)) => {
                     let player_is_not_too_far_away_from_wall =
@@ -166,6 +237,235 @@ impl<'s> System<'s> for UseToolSystem {
     }
 }
 
+/// Counts down the fuse of all placed dynamite. When a fuse runs out, the dynamite explodes and
+/// breaks all breakable blocks in its blast area.
+pub struct DynamiteSystem;
+
+impl<'s> System<'s> for DynamiteSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Write<'s, EventChannel<SoundEvent>>,
+        WriteStorage<'s, Dynamite>,
+        ReadStorage<'s, Block>,
+        ReadStorage<'s, Player>,
+        Write<'s, TileMap>,
+        Read<'s, Time>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (mut sound_channel, mut dynamites, blocks, players, mut tile_map, time, entities): Self::SystemData,
+    ) {
+        // The blocks broken by all dynamite that explodes this tick. Every blast area is worked
+        // out on the level as it was at the start of the tick, the same as the blast preview
+        // showed it, so the order in which the dynamite explodes does not matter.
+        let mut broken = vec![];
+        for (dynamite, entity) in (&mut dynamites, &entities).join() {
+            dynamite.fuse -= time.fixed_seconds();
+            if dynamite.fuse <= 0. {
+                sound_channel.single_write(SoundEvent::new(SoundType::Mining));
+                broken.extend(
+                    blast_area(&tile_map, dynamite.pos, dynamite.radius)
+                        .into_iter()
+                        .filter(|pos| {
+                            tile_map
+                                .get_tile(pos)
+                                .map(|tile| tile.is_breakable())
+                                .unwrap_or(false)
+                        }),
+                );
+                entities.delete(entity).expect("Failed to delete dynamite.");
+            }
+        }
+        // Blocks hit by more than one explosion are only broken once.
+        broken.sort();
+        broken.dedup();
+        broken.iter().for_each(|pos| tile_map.remove_tile(pos));
+        for (block, entity, _) in (&blocks, &entities, !&players).join() {
+            if broken.contains(&block.pos) {
+                entities.delete(entity).expect("Failed to delete block!");
+            }
+        }
+    }
+}
+
+/// Shows which tiles will be hit by an explosion: those around every placed stick of dynamite, and
+/// those around the player if they are carrying dynamite and could place it right now.
+#[derive(Default)]
+pub struct BlastPreviewSystem {
+    /// The positions that are currently marked.
+    shown: Vec<Pos>,
+}
+
+impl<'s> System<'s> for BlastPreviewSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, Steering>,
+        ReadStorage<'s, Dynamite>,
+        Read<'s, TileMap>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(&mut self, (players, steerings, dynamites, tile_map, lazy): Self::SystemData) {
+        let mut affected = dynamites
+            .join()
+            .flat_map(|dynamite| blast_area(&tile_map, dynamite.pos, dynamite.radius))
+            .collect::<Vec<Pos>>();
+        for (player, steering) in (&players, &steerings).join() {
+            if let Some(ToolType::Dynamite { radius, .. }) = player.equipped {
+                if steering.is_grounded() {
+                    affected.extend(blast_area(&tile_map, dynamite_placement(steering), radius));
+                }
+            }
+        }
+        affected.sort();
+        affected.dedup();
+        if affected != self.shown {
+            self.shown = affected.clone();
+            lazy.exec_mut(move |world| show_blast_preview(world, &affected));
+        }
+    }
+}
+
+/// Replaces all blast preview markers with markers on the given positions.
+fn show_blast_preview(world: &mut World, positions: &[Pos]) {
+    let old_markers = world.exec(
+        |(entities, markers): (Entities, ReadStorage<BlastPreviewTag>)| {
+            (&entities, &markers)
+                .join()
+                .map(|(entity, _)| entity)
+                .collect::<Vec<Entity>>()
+        },
+    );
+    if let Err(err) = world.delete_entities(&old_markers) {
+        error!("Failed to delete blast preview: {:?}", err);
+    }
+    // A headless simulation has no assets, there is nothing to show there.
+    if !world.has_value::<Assets>() {
+        return;
+    }
+    for pos in positions {
+        let asset = AssetType::Still(SpriteType::BlastPreview, 0);
+        let transform = load_transform(pos, &DepthLayer::UiElements, &Pos::new(1, 1), &asset);
+        let sprite = load_asset_from_world(&SpriteType::BlastPreview, 0, world);
+        world
+            .create_entity()
+            .with(BlastPreviewTag)
+            .with(transform)
+            .with(sprite)
+            .build();
+    }
+}
+
+/// Creates the entity for a placed stick of dynamite.
+pub fn build_dynamite(world: &mut World, dynamite: Dynamite) {
+    let sprite = world
+        .read_resource::<TileMap>()
+        .tile_defs()
+        .tool_sprite(dynamite.tool());
+    let transform = sprite.map(|(sprite, sprite_nr)| {
+        load_transform(
+            &dynamite.pos,
+            &DepthLayer::FloatingBlocks,
+            &Pos::new(1, 1),
+            &AssetType::Still(sprite, sprite_nr),
+        )
+    });
+    // A headless simulation has no assets, the dynamite is not drawn there.
+    let render = sprite
+        .filter(|_| world.has_value::<Assets>())
+        .map(|(sprite, sprite_nr)| load_asset_from_world(&sprite, sprite_nr, world));
+    let mut builder = world.create_entity().with(dynamite);
+    if let Some(transform) = transform {
+        builder = builder.with(transform);
+    }
+    if let Some(render) = render {
+        builder = builder.with(render);
+    }
+    builder.build();
+}
+
+/// Returns the anchors of the ladder segments that the player would place, or None if the ladder
+/// does not fit.
+///
+/// The ladder is placed in the columns right in front of the player. If there is no floor there,
+/// it hangs down from the player's floor level so the player can climb down it. Otherwise it
+/// stands on that floor and reaches upwards.
+fn ladder_placement(
+    steering: &Steering,
+    tile_map: &TileMap,
+    segment_key: &str,
+    length: u8,
+) -> Option<Vec<Pos>> {
+    let segment_dimens = tile_map.tile_defs().get(segment_key).dimens;
+    let front_x = if steering.facing.x.is_positive() {
+        steering.pos.x + steering.dimens.x
+    } else {
+        steering.pos.x - segment_dimens.x
+    };
+    let over_ledge = (0..segment_dimens.x).all(|x| {
+        tile_map
+            .get_tile(&Pos::new(front_x + x, steering.pos.y - 1))
+            .map(|tile| !tile.provides_platform())
+            .unwrap_or(true)
+    });
+    let anchors = (0..length as i32)
+        .map(|segment| {
+            let y = if over_ledge {
+                steering.pos.y - segment_dimens.y * (segment + 1)
+            } else {
+                steering.pos.y + segment_dimens.y * segment
+            };
+            Pos::new(front_x, y)
+        })
+        .collect::<Vec<Pos>>();
+    let fits = anchors.iter().all(|anchor| {
+        (0..segment_dimens.x).all(|x| {
+            (0..segment_dimens.y).all(|y| tile_map.get_tile(&anchor.append_xy(x, y)).is_none())
+        })
+    });
+    if fits && !anchors.is_empty() {
+        Some(anchors)
+    } else {
+        None
+    }
+}
+
+/// The position where the player places dynamite: at their feet, on the side they are facing.
+pub fn dynamite_placement(steering: &Steering) -> Pos {
+    let facing_offset = if steering.facing.x.is_positive() {
+        steering.dimens.x - 1
+    } else {
+        0
+    };
+    Pos::new(steering.pos.x + facing_offset, steering.pos.y)
+}
+
+/// Returns every position hit by an explosion at the given position, in a cross pattern with the
+/// given radius.
+///
+/// Each arm of the cross stops at the first breakable block, which is hit, and before the first
+/// other tile that collides horizontally, which is not hit.
+pub fn blast_area(tile_map: &TileMap, center: Pos, radius: u8) -> Vec<Pos> {
+    let mut area = vec![center];
+    for (dx, dy) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        for distance in 1..=(radius as i32) {
+            let pos = center.append_xy(dx * distance, dy * distance);
+            match tile_map.get_tile(&pos) {
+                Some(tile) if tile.is_breakable() => {
+                    area.push(pos);
+                    break;
+                }
+                Some(tile) if tile.collides_horizontally() => break,
+                _ => area.push(pos),
+            }
+        }
+    }
+    area
+}
+
 fn at_least_one_is_breakable(blocks: &[Pos], tile_map: &TileMap) -> bool {
     blocks.iter().any(|pos| {
         tile_map
@@ -0,0 +1,109 @@
+use crate::components::*;
+use crate::levels::*;
+use crate::resources::*;
+use crate::systems::delete_entities_at;
+use amethyst::core::ecs::LazyUpdate;
+use amethyst::ecs::prelude::{Join, Read, ReadStorage, System, World, Write};
+use amethyst::prelude::WorldExt;
+use std::collections::BTreeSet;
+
+/// Fires and releases triggers when the player steps onto or off them. A pushable block that lies
+/// on a trigger presses it too. Whenever that changes which triggers are fired, the linked tiles
+/// are toggled.
+pub struct TriggerSystem;
+
+impl<'s> System<'s> for TriggerSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, Steering>,
+        ReadStorage<'s, Pushable>,
+        ReadStorage<'s, Block>,
+        ReadStorage<'s, Trigger>,
+        Write<'s, Triggers>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (players, steerings, pushables, blocks, triggers, mut trigger_state, lazy): Self::SystemData,
+    ) {
+        let pressed = triggers
+            .join()
+            .filter(|trigger| {
+                (&players, &steerings)
+                    .join()
+                    .any(|(_, steering)| trigger.is_overlapped_by(steering))
+                    || (&pushables, &blocks)
+                        .join()
+                        .any(|(pushable, block)| trigger.is_covered_by(pushable, block.pos))
+            })
+            .map(|trigger| trigger.pos)
+            .collect::<BTreeSet<Pos>>();
+        let mut fired = trigger_state.fired.clone();
+        for trigger in triggers.join() {
+            let is_pressed = pressed.contains(&trigger.pos);
+            let was_pressed = trigger_state.pressed.contains(&trigger.pos);
+            match trigger.kind {
+                TriggerKind::PressurePlate => {
+                    if is_pressed {
+                        fired.insert(trigger.pos);
+                    } else {
+                        fired.remove(&trigger.pos);
+                    }
+                }
+                TriggerKind::Tripwire => {
+                    if is_pressed {
+                        fired.insert(trigger.pos);
+                    }
+                }
+                TriggerKind::Lever => {
+                    if is_pressed && !was_pressed && !fired.remove(&trigger.pos) {
+                        fired.insert(trigger.pos);
+                    }
+                }
+            }
+        }
+        trigger_state.pressed = pressed;
+        if fired != trigger_state.fired {
+            trigger_state.fired = fired;
+            lazy.exec_mut(apply_triggers);
+        }
+    }
+}
+
+/// Brings the TileMap and the Block entities in line with the triggers that are fired: hides and
+/// shows the targeted tiles, and flips their side collision.
+pub fn apply_triggers(world: &mut World) {
+    let (hidden, flipped) = {
+        let triggers = world.read_resource::<Triggers>();
+        (triggers.hidden_targets(), triggers.flipped_targets())
+    };
+    let (to_hide, to_show) = {
+        let mut tile_map = world.write_resource::<TileMap>();
+        tile_map.set_flipped(flipped);
+        let currently_hidden = tile_map.hidden_tiles().copied().collect::<BTreeSet<Pos>>();
+        let to_hide = hidden
+            .difference(&currently_hidden)
+            .copied()
+            .collect::<BTreeSet<Pos>>();
+        to_hide.iter().for_each(|anchor| tile_map.hide_tile(anchor));
+        let to_show = currently_hidden
+            .difference(&hidden)
+            .filter_map(|anchor| tile_map.show_tile(anchor).map(|key| (*anchor, key)))
+            .collect::<Vec<(Pos, String)>>();
+        (to_hide, to_show)
+    };
+    // The player and mobs also have a Block, at the position they spawned at. Leave them be.
+    delete_entities_at(world, &to_hide, |world, entity| {
+        world.read_storage::<Steering>().get(entity).is_none()
+    });
+    for (anchor, key) in to_show {
+        let tile_def = world
+            .read_resource::<TileMap>()
+            .tile_defs()
+            .get(&key)
+            .clone();
+        build_tile(world, &anchor, &key, &tile_def);
+    }
+}
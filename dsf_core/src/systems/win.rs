@@ -86,8 +86,59 @@ impl<'s> System<'s> for KeyCollectionSystem {
     }
 }
 
+/// Checks if any mob touches the player. If so, the player dies. The mob's contact rule decides
+/// whether the level is reset or lost once the death animation is over.
+#[derive(Default)]
+pub struct MobContactSystem;
+
+impl<'s> System<'s> for MobContactSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'s, Player>,
+        ReadStorage<'s, Mob>,
+        ReadStorage<'s, Steering>,
+        ReadStorage<'s, Transform>,
+        Write<'s, WinCondition>,
+    );
+
+    fn run(&mut self, (players, mobs, steerings, transforms, mut win): Self::SystemData) {
+        if win.reached_open_door || win.is_dying() {
+            return;
+        }
+        let player_collider = (&players, &steerings, &transforms)
+            .join()
+            .map(|(_, steering, transform)| {
+                (
+                    Vector2::new(transform.translation().x, transform.translation().y),
+                    Vector2::new(steering.dimens.x as f32, steering.dimens.y as f32),
+                )
+            })
+            .next();
+        if let Some((pos, dimens)) = player_collider {
+            let contact_rule = (&mobs, &steerings, &transforms)
+                .join()
+                .find(|(_, mob_steering, mob_transform)| {
+                    let mob_x = mob_transform.translation().x;
+                    let mob_y = mob_transform.translation().y;
+                    let mob_width = mob_steering.dimens.x as f32;
+                    let mob_height = mob_steering.dimens.y as f32;
+                    pos.x - dimens.x / 2. < mob_x + mob_width / 3.
+                        && pos.x + dimens.x / 2. > mob_x - mob_width / 3.
+                        && pos.y - dimens.y / 2. < mob_y + mob_height / 3.
+                        && pos.y + dimens.y / 2. > mob_y - mob_height / 3.
+                })
+                .map(|(mob, _, _)| mob.on_contact);
+            if let Some(contact_rule) = contact_rule {
+                info!("Player was caught by a mob, applying {:?}.", contact_rule);
+                win.killed_by = Some(DeathCause::Mob(contact_rule));
+            }
+        }
+    }
+}
+
 /// Checks if the player has finished the level.
-/// The player finishes the level when they collect all keys and then reach the exit door.
+/// The player finishes the level when they collect all keys and then reach one of the exit doors.
+/// The id of that door is recorded in the WinCondition.
 #[derive(Default)]
 pub struct WinSystem;
 
@@ -99,13 +150,14 @@ impl<'s> System<'s> for WinSystem {
         ReadStorage<'s, Steering>,
         ReadStorage<'s, ExitDoor>,
         ReadStorage<'s, Transform>,
+        Read<'s, TileMap>,
         Write<'s, WinCondition>,
         Read<'s, LazyUpdate>,
     );
 
     fn run(
         &mut self,
-        (mut sound_channel, players, steerings, doors, transforms, mut win, lazy): Self::SystemData,
+        (mut sound_channel, players, steerings, doors, transforms, tile_map, mut win, lazy): Self::SystemData,
     ) {
         if win.reached_open_door || !win.all_keys_collected() {
             return;
@@ -120,20 +172,23 @@ impl<'s> System<'s> for WinSystem {
             })
             .next();
         if let Some((pos, dimens)) = player_collider {
-            for (_, door_transform) in (&doors, &transforms).join() {
+            let reached_door = (&doors, &transforms).join().find(|(_, door_transform)| {
                 let door_x = door_transform.translation().x;
                 let door_y = door_transform.translation().y;
-                if pos.x - dimens.x / 2. < door_x + DOOR_WIDTH / 3.
+                pos.x - dimens.x / 2. < door_x + DOOR_WIDTH / 3.
                     && pos.x + dimens.x / 2. > door_x - DOOR_WIDTH / 3.
                     && pos.y - dimens.y / 2. < door_y + DOOR_HEIGHT / 3.
                     && pos.y + dimens.y / 2. > door_y - DOOR_HEIGHT / 3.
-                {
-                    sound_channel.single_write(SoundEvent::new(SoundType::Win));
-                    win.reached_open_door = true;
-                    lazy.exec_mut(move |world| {
-                        UiHandles::add_ui(&UiType::WinMessage, world);
-                    });
-                }
+            });
+            if let Some((door, _)) = reached_door {
+                let exit = tile_map.exit_id(&door.pos);
+                info!("Player left the level through exit {:?}.", exit);
+                sound_channel.single_write(SoundEvent::new(SoundType::Win));
+                win.reached_open_door = true;
+                win.exit_taken = Some(exit);
+                lazy.exec_mut(move |world| {
+                    UiHandles::add_ui(&UiType::WinMessage, world);
+                });
             }
         }
     }
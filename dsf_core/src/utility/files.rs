@@ -1,4 +1,5 @@
 use amethyst::utils::application_root_dir;
+use std::env;
 use std::fs;
 use std::path::PathBuf;
 
@@ -26,8 +27,14 @@ pub fn get_assets_dir() -> PathBuf {
     get_root_dir().join("assets/")
 }
 
+/// If this environment variable is set, it overrides the root directory. This is meant for tools
+/// such as dsf_checks, which are not run from the same directory as the game.
+pub const ROOT_DIR_ENV_VAR: &str = "DSF_ROOT_DIR";
+
 fn get_root_dir() -> PathBuf {
-    application_root_dir().expect("Root directory not found!")
+    env::var_os(ROOT_DIR_ENV_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| application_root_dir().expect("Root directory not found!"))
 }
 
 fn create_if_missing(path: PathBuf) -> PathBuf {
@@ -55,3 +62,25 @@ pub fn get_user_cache_file() -> PathBuf {
 pub fn get_user_settings_dir() -> PathBuf {
     create_if_missing(get_user_data_dir().join("settings/"))
 }
+
+/// Recorded inputs are stored here. Replays are written here automatically whenever the player
+/// leaves or restarts a level.
+pub fn get_replays_dir() -> PathBuf {
+    create_if_missing(get_user_data_dir().join("replays/"))
+}
+
+/// The progress of levels that the player left before finishing them is stored here, one file
+/// per level.
+pub fn get_saves_dir() -> PathBuf {
+    create_if_missing(get_user_data_dir().join("saves/"))
+}
+
+/// Returns a hash of the given bytes.
+///
+/// This uses FNV-1a rather than the standard library's hasher, because the output of this hash
+/// is written to disk and must therefore be stable across compiler versions.
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -1,8 +1,12 @@
 use crate::resources::{Brush, LevelEdit, Selection};
+use dsf_core::levels::LevelViolation;
 
 #[derive(Debug, Default)]
 pub struct EditorData {
     pub level: LevelEdit,
     pub brush: Brush,
     pub selection: Selection,
+    /// The rules that the level broke when it was last loaded or saved. These are shown to the
+    /// designer, so they can fix them before the level is played.
+    pub problems: Vec<LevelViolation>,
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::resources::TileEdit;
 use dsf_core::components::Pos;
@@ -12,7 +12,11 @@ use dsf_core::levels::*;
 pub struct LevelEdit {
     pub pos: Pos,
     pub dimens: Pos,
+    pub wrapping: Wrapping,
+    pub hazards: HazardSettings,
     pub tile_map: HashMap<Pos, TileEdit>,
+    /// Title, author and other details of the level. Edited in the level details dialog.
+    pub metadata: LevelMetadata,
 }
 
 impl Default for LevelEdit {
@@ -20,7 +24,10 @@ impl Default for LevelEdit {
         LevelEdit {
             pos: Pos::new(-20, -10),
             dimens: Pos::new(40, 20),
+            wrapping: Wrapping::default(),
+            hazards: HazardSettings::default(),
             tile_map: HashMap::default(),
+            metadata: LevelMetadata::default(),
         }
     }
 }
@@ -45,13 +52,24 @@ impl LevelEdit {
 impl From<LevelEdit> for Level {
     fn from(mut item: LevelEdit) -> Self {
         let mut map = HashMap::new();
+        let mut properties = BTreeMap::new();
         item.tile_map.drain().for_each(|(key, val)| {
+            if !val.properties.is_empty() {
+                properties.insert(key, val.properties);
+            }
             map.insert(key, val.tile_def_key);
         });
         Level {
             pos: item.pos,
             dimens: item.dimens,
+            wrapping: item.wrapping,
+            hazards: item.hazards,
             tiles: map,
+            properties,
+            metadata: LevelMetadata {
+                format_version: CURRENT_FORMAT_VERSION,
+                ..item.metadata
+            },
         }
     }
 }
@@ -59,13 +77,21 @@ impl From<LevelEdit> for Level {
 impl From<Level> for LevelEdit {
     fn from(mut item: Level) -> Self {
         let mut map = HashMap::new();
+        let mut properties = item.properties;
         item.tiles.drain().for_each(|(key, val)| {
-            map.insert(key, TileEdit::new(val));
+            let mut tile_edit = TileEdit::new(val);
+            if let Some(properties) = properties.remove(&key) {
+                tile_edit.properties = properties;
+            }
+            map.insert(key, tile_edit);
         });
         LevelEdit {
             pos: item.pos,
             dimens: item.dimens,
+            wrapping: item.wrapping,
+            hazards: item.hazards,
             tile_map: map,
+            metadata: item.metadata,
         }
     }
 }
@@ -1,6 +1,10 @@
+use dsf_core::levels::TileProperties;
+
 #[derive(Debug, Clone, Default)]
 pub struct TileEdit {
     pub tile_def_key: String,
+    /// Properties of this particular tile. Placing a different tile here discards them.
+    pub properties: TileProperties,
     pub dirty: bool,
 }
 
@@ -8,6 +12,7 @@ impl TileEdit {
     pub fn new(tile_def_key: String) -> Self {
         TileEdit {
             tile_def_key,
+            properties: TileProperties::default(),
             dirty: true,
         }
     }
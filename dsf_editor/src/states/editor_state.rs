@@ -15,6 +15,7 @@ use dsf_precompile::AnimationId;
 
 use crate::resources::*;
 use crate::states::file_actions::{auto_save, auto_save_file, load_auto_save};
+use crate::states::{LevelDetailsState, TilePropertiesState};
 use crate::systems;
 
 use amethyst::core::ecs::shrev::EventChannel;
@@ -52,6 +53,7 @@ impl<'a, 'b> EditorState {
                     "tile_paint_system",
                     &["selection_system"],
                 )
+                .with(systems::LevelProblemsSystem, "level_problems_system", &[])
                 .build(),
         }
     }
@@ -60,6 +62,7 @@ impl<'a, 'b> EditorState {
     fn setup(&self, world: &mut World) {
         UiHandles::add_ui(&UiType::Fps, world);
         // UiHandles::add_ui(&UiType::Editor, world);
+        UiHandles::add_ui(&UiType::LevelProblems, world);
         setup_debug_lines(world);
         create_camera(world);
         let mut editor_data = EditorData::default();
@@ -69,6 +72,8 @@ impl<'a, 'b> EditorState {
         }
         let tile_defs = load_tile_definitions().expect("Tile definitions failed to load!");
         editor_data.brush.set_palette(&tile_defs);
+        let level: Level = editor_data.level.clone().into();
+        editor_data.problems = level.validate(&tile_defs);
         world.insert(editor_data);
         world.insert(tile_defs);
     }
@@ -127,6 +132,34 @@ impl SimpleState for EditorState {
                     auto_save(data.world).expect("Failed to auto-save level!");
                     Trans::Push(Box::new(PlayState::new(auto_save_file())))
                 }
+                InputEvent::KeyReleased {
+                    key_code: VirtualKeyCode::F2,
+                    scancode: _,
+                } => {
+                    auto_save(data.world).expect("Failed to auto-save level!");
+                    Trans::Push(Box::new(LevelDetailsState::default()))
+                }
+                InputEvent::KeyReleased {
+                    key_code: VirtualKeyCode::F3,
+                    scancode: _,
+                } => {
+                    let (cursor, has_tile) = {
+                        let editor_data = data.world.read_resource::<EditorData>();
+                        let cursor = editor_data.selection.end;
+                        (cursor, editor_data.level.tile_map.contains_key(&cursor))
+                    };
+                    if has_tile {
+                        auto_save(data.world).expect("Failed to auto-save level!");
+                        Trans::Push(Box::new(TilePropertiesState::new(cursor)))
+                    } else {
+                        warn!(
+                            "No tile has its bottom-left corner at ({}, {}), so there are no \
+                            properties to edit.",
+                            cursor.x, cursor.y
+                        );
+                        Trans::None
+                    }
+                }
                 _ => Trans::None,
             },
         }
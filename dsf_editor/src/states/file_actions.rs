@@ -2,7 +2,7 @@ use crate::resources::{EditorData, LevelEdit};
 use amethyst::config::ConfigError;
 use amethyst::prelude::{Config, World, WorldExt};
 
-use dsf_core::levels::Level;
+use dsf_core::levels::{Level, TileDefinitions};
 use dsf_core::utility::files::get_levels_dir;
 use std::path::PathBuf;
 
@@ -30,7 +30,7 @@ pub fn load(name: String) -> Result<LevelEdit, ConfigError> {
 }
 
 fn read_level_file(level_file: PathBuf) -> Result<LevelEdit, ConfigError> {
-    let level = Level::load(level_file)?;
+    let level = Level::load_migrated(level_file)?;
     Ok(level.into())
 }
 
@@ -50,8 +50,14 @@ pub fn save(name: String, world: &mut World) -> Result<(), ConfigError> {
     write_level_file(level_file, world)
 }
 
+/// Validates the level before writing it. Violations are shown to the designer, but the level is
+/// written regardless, so that work in progress is never lost.
 fn write_level_file(level_file: PathBuf, world: &mut World) -> Result<(), ConfigError> {
-    let data = world.write_resource::<EditorData>();
-    let level: Level = (*data).level.clone().into();
+    let level: Level = world.read_resource::<EditorData>().level.clone().into();
+    let problems = level.validate(&world.read_resource::<TileDefinitions>());
+    problems
+        .iter()
+        .for_each(|violation| warn!("Level {:?} is not valid: {}", level_file, violation));
+    world.write_resource::<EditorData>().problems = problems;
     level.write(level_file)
 }
@@ -0,0 +1,184 @@
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use amethyst::core::ecs::{ReadStorage, WriteStorage};
+use amethyst::prelude::WorldExt;
+use amethyst::ui::{UiFinder, UiText};
+use amethyst::{
+    ecs::prelude::{Entity, World},
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
+};
+
+use crate::resources::EditorData;
+use crate::states::file_actions::auto_save;
+use dsf_core::levels::{Difficulty, LevelMetadata};
+use dsf_core::resources::{UiHandles, UiType};
+use dsf_core::states::window_event_handler;
+
+const FIELD_TITLE: &str = "field_title";
+const FIELD_AUTHOR: &str = "field_author";
+const FIELD_DESCRIPTION: &str = "field_description";
+const FIELD_DIFFICULTY: &str = "field_difficulty";
+const FIELD_PAR_SECONDS: &str = "field_par_seconds";
+const FIELD_PAR_MOVES: &str = "field_par_moves";
+const FIELD_MUSIC: &str = "field_music";
+
+/// Dialog in which the title, author and other details of the level are edited.
+/// Pushed on top of the EditorState. When the dialog is closed, the details are stored in the
+/// level and the level is auto-saved.
+#[derive(Default)]
+pub struct LevelDetailsState {
+    /// The text field entities, each with the id of their field.
+    fields: Vec<(&'static str, Entity)>,
+}
+
+impl LevelDetailsState {
+    fn init_ui(&mut self, data: StateData<GameData>) {
+        UiHandles::add_ui(&UiType::LevelDetails, data.world);
+        // invoke a world update to finish creating our ui entities
+        data.data.update(&data.world);
+        data.world.exec(|ui_finder: UiFinder<'_>| {
+            self.fields = [
+                FIELD_TITLE,
+                FIELD_AUTHOR,
+                FIELD_DESCRIPTION,
+                FIELD_DIFFICULTY,
+                FIELD_PAR_SECONDS,
+                FIELD_PAR_MOVES,
+                FIELD_MUSIC,
+            ]
+            .iter()
+            .filter_map(|id| ui_finder.find(id).map(|entity| (*id, entity)))
+            .collect();
+        });
+        let metadata = data
+            .world
+            .read_resource::<EditorData>()
+            .level
+            .metadata
+            .clone();
+        let fields = &self.fields;
+        data.world.exec(|mut ui_text: WriteStorage<UiText>| {
+            for (id, entity) in fields {
+                if let Some(text_component) = ui_text.get_mut(*entity) {
+                    text_component.text = field_text(&metadata, id);
+                }
+            }
+        });
+    }
+
+    /// Reads the text fields and stores their contents in the level.
+    fn apply(&self, world: &mut World) {
+        let mut metadata = world.read_resource::<EditorData>().level.metadata.clone();
+        world.exec(|ui_text: ReadStorage<UiText>| {
+            for (id, entity) in &self.fields {
+                if let Some(text_component) = ui_text.get(*entity) {
+                    apply_field(&mut metadata, id, &text_component.text);
+                }
+            }
+        });
+        world.write_resource::<EditorData>().level.metadata = metadata;
+    }
+}
+
+impl SimpleState for LevelDetailsState {
+    fn on_start(&mut self, data: StateData<GameData>) {
+        info!("LevelDetailsState on_start");
+        self.init_ui(data);
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData>) {
+        info!("LevelDetailsState on_stop");
+        self.apply(data.world);
+        auto_save(data.world).expect("Failed to auto-save level!");
+        data.world.delete_all();
+    }
+
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+        window_event_handler::handle(&event, data.world);
+        match event {
+            StateEvent::Window(event) => {
+                if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
+                    Trans::Pop
+                } else {
+                    Trans::None
+                }
+            }
+            _ => Trans::None,
+        }
+    }
+}
+
+/// The text shown in the field with the given id. Details that the level does not have are
+/// shown as an empty field.
+fn field_text(metadata: &LevelMetadata, id: &str) -> String {
+    match id {
+        FIELD_TITLE => metadata.title.clone(),
+        FIELD_AUTHOR => metadata.author.clone(),
+        FIELD_DESCRIPTION => metadata.description.clone(),
+        FIELD_DIFFICULTY => metadata
+            .difficulty
+            .map(|difficulty| format!("{:?}", difficulty)),
+        FIELD_PAR_SECONDS => metadata.par_seconds.map(|seconds| seconds.to_string()),
+        FIELD_PAR_MOVES => metadata.par_moves.map(|moves| moves.to_string()),
+        FIELD_MUSIC => metadata.music.clone(),
+        _ => None,
+    }
+    .unwrap_or_default()
+}
+
+/// Stores the text of the field with the given id in the metadata. An empty field clears the
+/// detail. If the text cannot be understood, the detail is left as it was.
+fn apply_field(metadata: &mut LevelMetadata, id: &str, text: &str) {
+    let text = text.trim();
+    match id {
+        FIELD_TITLE => metadata.title = optional_text(text),
+        FIELD_AUTHOR => metadata.author = optional_text(text),
+        FIELD_DESCRIPTION => metadata.description = optional_text(text),
+        FIELD_DIFFICULTY => {
+            metadata.difficulty = parse_optional(id, text, metadata.difficulty, |text| {
+                Difficulty::ALL
+                    .iter()
+                    .find(|difficulty| format!("{:?}", difficulty).eq_ignore_ascii_case(text))
+                    .copied()
+            })
+        }
+        FIELD_PAR_SECONDS => {
+            metadata.par_seconds = parse_optional(id, text, metadata.par_seconds, |text| {
+                f32::from_str(text).ok().filter(|seconds| *seconds > 0.)
+            })
+        }
+        FIELD_PAR_MOVES => {
+            metadata.par_moves = parse_optional(id, text, metadata.par_moves, |text| {
+                u32::from_str(text).ok()
+            })
+        }
+        FIELD_MUSIC => metadata.music = optional_text(text),
+        _ => (),
+    }
+}
+
+fn optional_text(text: &str) -> Option<String> {
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+fn parse_optional<T: Debug, F>(id: &str, text: &str, old: Option<T>, parse: F) -> Option<T>
+where
+    F: Fn(&str) -> Option<T>,
+{
+    if text.is_empty() {
+        return None;
+    }
+    parse(text).or_else(|| {
+        warn!(
+            "Could not understand {:?} in {}, keeping {:?}.",
+            text, id, old
+        );
+        old
+    })
+}
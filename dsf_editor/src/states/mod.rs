@@ -1,4 +1,8 @@
 mod editor_state;
 mod file_actions;
+mod level_details;
+mod tile_properties;
 
 pub use self::editor_state::EditorState;
+pub use self::level_details::LevelDetailsState;
+pub use self::tile_properties::TilePropertiesState;
@@ -0,0 +1,120 @@
+use amethyst::core::ecs::{ReadStorage, WriteStorage};
+use amethyst::prelude::WorldExt;
+use amethyst::ui::{UiFinder, UiText};
+use amethyst::{
+    ecs::prelude::{Entity, World},
+    input::{is_close_requested, is_key_down, VirtualKeyCode},
+    GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans,
+};
+
+use crate::resources::EditorData;
+use crate::states::file_actions::auto_save;
+use dsf_core::components::Pos;
+use dsf_core::levels::TileProperties;
+use dsf_core::resources::{UiHandles, UiType};
+use dsf_core::states::window_event_handler;
+
+const LABEL_TILE: &str = "label_tile_properties_tile";
+const FIELD_PROPERTIES: &str = "field_properties";
+
+/// Dialog in which the properties of a single tile are edited, such as the partner of a
+/// teleporter or the links of a trigger. Pushed on top of the EditorState for the tile at the
+/// cursor. When the dialog is closed, the properties are stored in the tile and the level is
+/// auto-saved.
+pub struct TilePropertiesState {
+    /// The anchor of the tile whose properties are edited.
+    pos: Pos,
+    field: Option<Entity>,
+}
+
+impl TilePropertiesState {
+    pub fn new(pos: Pos) -> Self {
+        TilePropertiesState { pos, field: None }
+    }
+
+    fn init_ui(&mut self, data: StateData<GameData>) {
+        UiHandles::add_ui(&UiType::TileProperties, data.world);
+        // invoke a world update to finish creating our ui entities
+        data.data.update(&data.world);
+        let (label, field) = data.world.exec(|ui_finder: UiFinder<'_>| {
+            (ui_finder.find(LABEL_TILE), ui_finder.find(FIELD_PROPERTIES))
+        });
+        self.field = field;
+        let (key, properties) = data
+            .world
+            .read_resource::<EditorData>()
+            .level
+            .tile_map
+            .get(&self.pos)
+            .map(|tile_edit| {
+                (
+                    tile_edit.tile_def_key.clone(),
+                    tile_edit.properties.to_text(),
+                )
+            })
+            .unwrap_or_default();
+        let caption = format!("{} at ({}, {})", key, self.pos.x, self.pos.y);
+        data.world.exec(|mut ui_text: WriteStorage<UiText>| {
+            if let Some(text_component) = label.and_then(|label| ui_text.get_mut(label)) {
+                text_component.text = caption;
+            }
+            if let Some(text_component) = field.and_then(|field| ui_text.get_mut(field)) {
+                text_component.text = properties;
+            }
+        });
+    }
+
+    /// Reads the text field and stores its contents in the tile. If the text cannot be
+    /// understood, the tile keeps the properties it had.
+    fn apply(&self, world: &mut World) {
+        let text = world.exec(|ui_text: ReadStorage<UiText>| {
+            self.field
+                .and_then(|field| ui_text.get(field))
+                .map(|text_component| text_component.text.clone())
+        });
+        let text = match text {
+            Some(text) => text,
+            None => return,
+        };
+        match TileProperties::from_text(&text) {
+            Ok(properties) => {
+                let mut editor_data = world.write_resource::<EditorData>();
+                if let Some(tile_edit) = editor_data.level.tile_map.get_mut(&self.pos) {
+                    tile_edit.properties = properties;
+                }
+            }
+            Err(err) => warn!(
+                "Could not understand tile properties {:?}, keeping the old ones: {}",
+                text, err
+            ),
+        }
+    }
+}
+
+impl SimpleState for TilePropertiesState {
+    fn on_start(&mut self, data: StateData<GameData>) {
+        info!("TilePropertiesState on_start");
+        self.init_ui(data);
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData>) {
+        info!("TilePropertiesState on_stop");
+        self.apply(data.world);
+        auto_save(data.world).expect("Failed to auto-save level!");
+        data.world.delete_all();
+    }
+
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+        window_event_handler::handle(&event, data.world);
+        match event {
+            StateEvent::Window(event) => {
+                if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
+                    Trans::Pop
+                } else {
+                    Trans::None
+                }
+            }
+            _ => Trans::None,
+        }
+    }
+}
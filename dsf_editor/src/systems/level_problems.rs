@@ -0,0 +1,37 @@
+use amethyst::{
+    ecs::prelude::{Read, System, WriteStorage},
+    ui::{UiFinder, UiText},
+};
+
+use crate::resources::EditorData;
+
+/// Lists the rules that the level breaks in the corner of the editor. Problems that keep the level
+/// from being played at all are marked, the game refuses to load a level that has them.
+pub struct LevelProblemsSystem;
+
+impl<'s> System<'s> for LevelProblemsSystem {
+    type SystemData = (WriteStorage<'s, UiText>, UiFinder<'s>, Read<'s, EditorData>);
+
+    fn run(&mut self, (mut ui_text, finder, editor_data): Self::SystemData) {
+        let label = finder
+            .find("label_level_problems")
+            .and_then(|entity| ui_text.get_mut(entity));
+        if let Some(label) = label {
+            label.text = if editor_data.problems.is_empty() {
+                String::new()
+            } else {
+                editor_data.problems.iter().fold(
+                    String::from("This level breaks these rules:"),
+                    |text, violation| {
+                        let marker = if violation.is_blocking() {
+                            "[cannot play]"
+                        } else {
+                            "-"
+                        };
+                        format!("{}\n{} {}", text, marker, violation)
+                    },
+                )
+            };
+        }
+    }
+}
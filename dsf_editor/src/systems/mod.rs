@@ -1,6 +1,7 @@
 mod choose_brush;
 mod cursor;
 mod cursor_preview;
+mod level_problems;
 mod place_tiles;
 mod selection;
 mod tile_paint;
@@ -8,6 +9,7 @@ mod tile_paint;
 pub use self::choose_brush::*;
 pub use self::cursor::*;
 pub use self::cursor_preview::*;
+pub use self::level_problems::*;
 pub use self::place_tiles::*;
 pub use self::selection::*;
 pub use self::tile_paint::*;
@@ -50,7 +50,7 @@ fn main() -> amethyst::Result<()> {
             &["camera_system"],
         )
         .with_system_desc(
-            DjSystemDesc::new(|music: &mut Music| music.music.next()),
+            DjSystemDesc::new(|music: &mut Music| music.next_track()),
             "dj",
             &[],
         )
@@ -2,7 +2,7 @@ use amethyst::prelude::WorldExt;
 use amethyst::ui::UiCreator;
 use amethyst::ui::UiLoader;
 use dsf_core::resources::{
-    load_audio_settings, load_debug_settings, Assets, AudioSettings, MovementConfig, Music,
+    load_audio_settings, load_debug_settings, load_movement_config, Assets, AudioSettings, Music,
     UiHandles, UserCache,
 };
 
@@ -20,6 +20,7 @@ use amethyst::audio::{AudioSink, Mp3Format, WavFormat};
 use dsf_core::utility::files::{get_config_dir, get_user_cache_file};
 use dsf_core::utility::loading_config::LoadingConfig;
 use dsf_editor::resources::EditorConfig;
+use std::path::Path;
 
 /// This state is briefly active when the game is first started up. It loads all assets used in the
 /// entire game and then switches to the main menu state.
@@ -119,13 +120,19 @@ impl SimpleState for LoadingState {
                     .music_tracks
                     .drain(..)
                     .map(|music_file_path| {
+                        let track_name = Path::new(&music_file_path)
+                            .file_name()
+                            .and_then(|name| name.to_str())
+                            .unwrap_or(&music_file_path)
+                            .to_string();
                         let loader = data.world.read_resource::<Loader>();
-                        loader.load(
+                        let handle = loader.load(
                             music_file_path,
                             Mp3Format,
                             &mut self.progress,
                             &data.world.read_resource(),
-                        )
+                        );
+                        (track_name, handle)
                     })
                     .collect();
                 Music::new(music_handles)
@@ -172,15 +179,7 @@ fn load_configs(world: &mut World) {
     world.insert(load_debug_settings());
     world.insert(load_audio_settings());
     let config_dir = get_config_dir();
-    world.insert(
-        MovementConfig::load(&config_dir.join("movement.ron")).unwrap_or_else(|error| {
-            error!(
-                "Failed to load movement config! Falling back to default. Error: {:?}",
-                error
-            );
-            MovementConfig::default()
-        }),
-    );
+    world.insert(load_movement_config());
     world.insert(
         EditorConfig::load(&config_dir.join("editor.ron")).unwrap_or_else(|error| {
             error!(